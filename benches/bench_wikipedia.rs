@@ -1,11 +1,36 @@
 use criterion::{criterion_group, criterion_main, Criterion};
 use html_parser::Dom;
 
-static HTML: &'static str = include_str!("./wikipedia-2020-12-21.html");
+static HTML: &str = include_str!("./wikipedia-2020-12-21.html");
 
 fn wikipedia(c: &mut Criterion) {
     c.bench_function("wikipedia", |b| b.iter(|| Dom::parse(HTML).unwrap()));
 }
 
-criterion_group!(benches, wikipedia);
+/// Start tags without a `>`, which the pest grammar reads again from every `<`
+fn unclosed_start_tags(c: &mut Criterion) {
+    let html = "<a b=".repeat(2_000);
+    c.bench_function("unclosed start tags", |b| {
+        b.iter(|| Dom::parse(&html).unwrap())
+    });
+}
+
+#[cfg(feature = "pest")]
+fn pest(c: &mut Criterion) {
+    use html_parser::{ParseOptions, Tokenizer};
+
+    let options = ParseOptions::new().tokenizer(Tokenizer::Pest);
+    c.bench_function("wikipedia (pest)", |b| {
+        b.iter(|| Dom::parse_with(HTML, &options).unwrap())
+    });
+    let html = "<a b=".repeat(2_000);
+    c.bench_function("unclosed start tags (pest)", |b| {
+        b.iter(|| Dom::parse_with(&html, &options).unwrap())
+    });
+}
+
+#[cfg(not(feature = "pest"))]
+criterion_group!(benches, wikipedia, unclosed_start_tags);
+#[cfg(feature = "pest")]
+criterion_group!(benches, wikipedia, unclosed_start_tags, pest);
 criterion_main!(benches);
@@ -0,0 +1,363 @@
+use super::node::Node;
+use super::span::SourceSpan;
+use super::{Dom, DomVariant};
+use crate::error::ParseError;
+use std::borrow::Cow;
+use std::ops::{Index, IndexMut};
+
+/// A stable handle to a node in an [`ArenaDom`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId(usize);
+
+impl NodeId {
+    /// The position of the node in the arena. Nodes converted from a [`Dom`] are stored in
+    /// document order, so this is also the position of the node in a depth first traversal.
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct ArenaNode<'a> {
+    node: Node<'a>,
+    parent: Option<NodeId>,
+    first_child: Option<NodeId>,
+    last_child: Option<NodeId>,
+    prev_sibling: Option<NodeId>,
+    next_sibling: Option<NodeId>,
+}
+
+/// An alternative representation of the [`Dom`] where all nodes are stored in a flat arena and
+/// linked to their parent and siblings, so that navigation in any direction is O(1).
+///
+/// The children of an element, or of a conditional comment, are linked through the arena, which
+/// means that `Element::children` of the nodes in the arena are always empty.
+///
+/// ```rust
+/// use html_parser::{Dom, Result};
+///
+/// fn main() -> Result<()> {
+///     let dom = Dom::parse("<form id='login'><div><input name='user'></div></form>")?;
+///     let arena = dom.into_arena();
+///
+///     let input = arena
+///         .iter()
+///         .find(|id| arena[*id].element().is_some_and(|e| e.name == "input"))
+///         .unwrap();
+///     let form = arena
+///         .ancestors(input)
+///         .find(|id| arena[*id].element().is_some_and(|e| e.name == "form"))
+///         .unwrap();
+///     assert_eq!(arena[form].element().unwrap().id.as_deref(), Some("login"));
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArenaDom<'a> {
+    /// The type of the tree that was parsed
+    pub tree_type: DomVariant,
+
+    /// A collection of all errors during parsing
+    pub errors: Vec<ParseError>,
+
+    /// The parsed input, which source spans refer to. Empty if source spans were disabled
+    pub source: Cow<'a, str>,
+
+    nodes: Vec<ArenaNode<'a>>,
+    first_root: Option<NodeId>,
+    last_root: Option<NodeId>,
+}
+
+impl<'a> ArenaDom<'a> {
+    /// Converts the tree into an arena, where the nodes are stored in document order
+    pub fn from_dom(dom: Dom<'a>) -> Self {
+        let mut arena = Self {
+            tree_type: dom.tree_type,
+            errors: dom.errors,
+            source: dom.source,
+            nodes: vec![],
+            first_root: None,
+            last_root: None,
+        };
+
+        // The tree is walked without recursion so that deep trees can't overflow the stack.
+        // Children are pushed in reverse so that they're popped, and added, in document order.
+        let mut stack = dom
+            .children
+            .into_iter()
+            .rev()
+            .map(|node| (None, node))
+            .collect::<Vec<_>>();
+        while let Some((parent, mut node)) = stack.pop() {
+            let children = node.children_mut().map(std::mem::take).unwrap_or_default();
+            let id = arena.push(parent, node);
+            stack.extend(children.into_iter().rev().map(|child| (Some(id), child)));
+        }
+        arena
+    }
+
+    /// Converts the arena back into a tree
+    pub fn into_dom(self) -> Dom<'a> {
+        let roots = self.roots().collect::<Vec<_>>();
+        let mut links = self
+            .nodes
+            .iter()
+            .map(|node| node.first_child)
+            .collect::<Vec<_>>();
+        let next_siblings = self
+            .nodes
+            .iter()
+            .map(|node| node.next_sibling)
+            .collect::<Vec<_>>();
+        let mut nodes = self
+            .nodes
+            .into_iter()
+            .map(|node| Some(node.node))
+            .collect::<Vec<_>>();
+
+        // Post-order walk, without recursion, where each element collects its already built
+        // children. `links[id]` is used as a cursor to the next child that has to be built.
+        let mut built: Vec<Vec<Node<'a>>> = vec![vec![]];
+        let mut stack: Vec<NodeId> = vec![];
+        let mut next_root = roots.into_iter();
+        loop {
+            let next = match stack.last() {
+                Some(parent) => links[parent.0].take(),
+                None => next_root.next(),
+            };
+            match next {
+                Some(id) => {
+                    if let Some(parent) = stack.last() {
+                        links[parent.0] = next_siblings[id.0];
+                    }
+                    stack.push(id);
+                    built.push(vec![]);
+                }
+                None => {
+                    let id = match stack.pop() {
+                        Some(id) => id,
+                        None => break,
+                    };
+                    let children = built.pop().expect("children of node");
+                    let mut node = nodes[id.0].take().expect("node is only built once");
+                    if let Some(node_children) = node.children_mut() {
+                        node_children.extend(children);
+                    }
+                    built.last_mut().expect("parent of node").push(node);
+                }
+            }
+        }
+
+        Dom {
+            tree_type: self.tree_type,
+            children: built.pop().expect("root nodes"),
+            errors: self.errors,
+            source: self.source,
+        }
+    }
+
+    fn push(&mut self, parent: Option<NodeId>, node: Node<'a>) -> NodeId {
+        let id = NodeId(self.nodes.len());
+        let prev_sibling = match parent {
+            Some(parent) => self.nodes[parent.0].last_child,
+            None => self.last_root,
+        };
+        self.nodes.push(ArenaNode {
+            node,
+            parent,
+            first_child: None,
+            last_child: None,
+            prev_sibling,
+            next_sibling: None,
+        });
+
+        if let Some(prev_sibling) = prev_sibling {
+            self.nodes[prev_sibling.0].next_sibling = Some(id);
+        }
+        match parent {
+            Some(parent) => {
+                let parent = &mut self.nodes[parent.0];
+                parent.first_child.get_or_insert(id);
+                parent.last_child = Some(id);
+            }
+            None => {
+                self.first_root.get_or_insert(id);
+                self.last_root = Some(id);
+            }
+        }
+        id
+    }
+
+    /// The text of a span in the parsed input, see [`SourceSpan`]
+    pub fn span_text(&self, span: &SourceSpan) -> &str {
+        span.text(&self.source)
+    }
+
+    /// The number of nodes in the arena
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn get(&self, id: NodeId) -> Option<&Node<'a>> {
+        self.nodes.get(id.0).map(|node| &node.node)
+    }
+
+    pub fn get_mut(&mut self, id: NodeId) -> Option<&mut Node<'a>> {
+        self.nodes.get_mut(id.0).map(|node| &mut node.node)
+    }
+
+    /// All nodes, at any depth, in document order
+    pub fn iter(&self) -> Descendants<'_> {
+        Descendants {
+            arena: self,
+            root: None,
+            next: self.first_root,
+        }
+    }
+
+    /// The root children of the tree
+    pub fn roots(&self) -> Siblings<'_> {
+        Siblings {
+            arena: self,
+            next: self.first_root,
+        }
+    }
+
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self.nodes[id.0].parent
+    }
+
+    pub fn first_child(&self, id: NodeId) -> Option<NodeId> {
+        self.nodes[id.0].first_child
+    }
+
+    pub fn last_child(&self, id: NodeId) -> Option<NodeId> {
+        self.nodes[id.0].last_child
+    }
+
+    pub fn next_sibling(&self, id: NodeId) -> Option<NodeId> {
+        self.nodes[id.0].next_sibling
+    }
+
+    pub fn prev_sibling(&self, id: NodeId) -> Option<NodeId> {
+        self.nodes[id.0].prev_sibling
+    }
+
+    /// The children of the node, in document order
+    pub fn children(&self, id: NodeId) -> Siblings<'_> {
+        Siblings {
+            arena: self,
+            next: self.first_child(id),
+        }
+    }
+
+    /// The ancestors of the node, starting with its parent
+    pub fn ancestors(&self, id: NodeId) -> Ancestors<'_> {
+        Ancestors {
+            arena: self,
+            next: self.parent(id),
+        }
+    }
+
+    /// The descendants of the node, not including the node itself, in document order
+    pub fn descendants(&self, id: NodeId) -> Descendants<'_> {
+        Descendants {
+            arena: self,
+            root: Some(id),
+            next: self.first_child(id),
+        }
+    }
+}
+
+impl<'a> Index<NodeId> for ArenaDom<'a> {
+    type Output = Node<'a>;
+
+    fn index(&self, id: NodeId) -> &Self::Output {
+        &self.nodes[id.0].node
+    }
+}
+
+impl IndexMut<NodeId> for ArenaDom<'_> {
+    fn index_mut(&mut self, id: NodeId) -> &mut Self::Output {
+        &mut self.nodes[id.0].node
+    }
+}
+
+impl<'a> From<Dom<'a>> for ArenaDom<'a> {
+    fn from(dom: Dom<'a>) -> Self {
+        Self::from_dom(dom)
+    }
+}
+
+impl<'a> From<ArenaDom<'a>> for Dom<'a> {
+    fn from(arena: ArenaDom<'a>) -> Self {
+        arena.into_dom()
+    }
+}
+
+/// Iterator over a node and its next siblings
+pub struct Siblings<'a> {
+    arena: &'a ArenaDom<'a>,
+    next: Option<NodeId>,
+}
+
+impl<'a> Iterator for Siblings<'a> {
+    type Item = NodeId;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let id = self.next?;
+        self.next = self.arena.next_sibling(id);
+        Some(id)
+    }
+}
+
+/// Iterator over the ancestors of a node
+pub struct Ancestors<'a> {
+    arena: &'a ArenaDom<'a>,
+    next: Option<NodeId>,
+}
+
+impl<'a> Iterator for Ancestors<'a> {
+    type Item = NodeId;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let id = self.next?;
+        self.next = self.arena.parent(id);
+        Some(id)
+    }
+}
+
+/// Depth first iterator over the descendants of a node, or the whole tree
+pub struct Descendants<'a> {
+    arena: &'a ArenaDom<'a>,
+    /// `None` when iterating over the whole tree
+    root: Option<NodeId>,
+    next: Option<NodeId>,
+}
+
+impl<'a> Iterator for Descendants<'a> {
+    type Item = NodeId;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let id = self.next?;
+
+        // Go down if possible, else to the next sibling of the node or its closest ancestor
+        self.next = self.arena.first_child(id);
+        let mut current = id;
+        while self.next.is_none() {
+            if let Some(next_sibling) = self.arena.next_sibling(current) {
+                self.next = Some(next_sibling);
+                break;
+            }
+            match self.arena.parent(current) {
+                Some(parent) if Some(parent) != self.root => current = parent,
+                _ => break,
+            }
+        }
+        Some(id)
+    }
+}
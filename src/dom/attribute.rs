@@ -0,0 +1,309 @@
+use super::span::SourceSpan;
+use serde::de::{Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{Serialize, SerializeMap, Serializer};
+use serde::Deserialize;
+use std::borrow::Cow;
+use std::fmt;
+use std::iter::FromIterator;
+use std::marker::PhantomData;
+use std::ops::Index;
+use std::result::Result;
+use std::slice::Iter;
+
+/// A single attribute, as it was written in the source
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Attribute<'a> {
+    /// The key of the attribute, ex: `href`
+    pub key: Cow<'a, str>,
+
+    /// The value of the attribute, `None` for attributes without a value, ex: `<input disabled>`
+    #[serde(default)]
+    pub value: Option<Cow<'a, str>>,
+
+    /// Span of the attribute in the parsed source
+    #[serde(default)]
+    pub source_span: SourceSpan,
+
+    /// Span of the key
+    #[serde(skip)]
+    pub key_span: SourceSpan,
+
+    /// Span of the value, without quotes
+    #[serde(skip)]
+    pub value_span: Option<SourceSpan>,
+}
+
+impl<'a> Attribute<'a> {
+    pub fn new(key: impl Into<Cow<'a, str>>, value: Option<Cow<'a, str>>) -> Self {
+        Self {
+            key: key.into(),
+            value,
+            source_span: SourceSpan::default(),
+            key_span: SourceSpan::default(),
+            value_span: None,
+        }
+    }
+
+    /// Detaches the attribute from the parsed input, see
+    /// [`Dom::into_owned`](crate::Dom::into_owned)
+    pub fn into_owned(self) -> Attribute<'static> {
+        Attribute {
+            key: Cow::Owned(self.key.into_owned()),
+            value: self.value.map(|value| Cow::Owned(value.into_owned())),
+            source_span: self.source_span,
+            key_span: self.key_span,
+            value_span: self.value_span,
+        }
+    }
+}
+
+/// The attributes of an element in source order, including duplicates.
+///
+/// Keys are compared ignoring ascii case, as in html. Lookups by key return the first attribute
+/// with that key, as browsers do, while [`Attributes::get_all`] and [`Attributes::duplicates`]
+/// give access to the repeated ones.
+#[derive(Clone, Default, PartialEq)]
+pub struct Attributes<'a> {
+    entries: Vec<Attribute<'a>>,
+}
+
+impl<'a> Attributes<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the value of the first attribute with the key
+    pub fn get(&self, key: &str) -> Option<&Option<Cow<'a, str>>> {
+        self.get_attribute(key).map(|attribute| &attribute.value)
+    }
+
+    /// Returns the first attribute with the key
+    pub fn get_attribute(&self, key: &str) -> Option<&Attribute<'a>> {
+        self.entries
+            .iter()
+            .find(|attribute| attribute.key.eq_ignore_ascii_case(key))
+    }
+
+    /// Returns all attributes with the key, in source order
+    pub fn get_all<'b>(&'b self, key: &'b str) -> impl Iterator<Item = &'b Attribute<'a>> {
+        self.entries
+            .iter()
+            .filter(move |attribute| attribute.key.eq_ignore_ascii_case(key))
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.get_attribute(key).is_some()
+    }
+
+    /// Sets the value of the first attribute with the key, or appends a new attribute if there is
+    /// none. The previous value is returned.
+    pub fn insert(
+        &mut self,
+        key: impl Into<Cow<'a, str>>,
+        value: Option<Cow<'a, str>>,
+    ) -> Option<Option<Cow<'a, str>>> {
+        let key = key.into();
+        match self
+            .entries
+            .iter_mut()
+            .find(|attribute| attribute.key.eq_ignore_ascii_case(&key))
+        {
+            Some(attribute) => Some(std::mem::replace(&mut attribute.value, value)),
+            None => {
+                self.entries.push(Attribute::new(key, value));
+                None
+            }
+        }
+    }
+
+    /// Appends an attribute, even if there already is one with the same key
+    pub fn push(&mut self, attribute: Attribute<'a>) {
+        self.entries.push(attribute);
+    }
+
+    /// Removes all attributes with the key, returning the value of the first one
+    pub fn remove(&mut self, key: &str) -> Option<Option<Cow<'a, str>>> {
+        let index = self
+            .entries
+            .iter()
+            .position(|attribute| attribute.key.eq_ignore_ascii_case(key))?;
+        let removed = self.entries.remove(index);
+        self.entries
+            .retain(|attribute| !attribute.key.eq_ignore_ascii_case(key));
+        Some(removed.value)
+    }
+
+    /// All attributes in source order, including duplicates
+    pub fn iter(&self) -> Iter<'_, Attribute<'a>> {
+        self.entries.iter()
+    }
+
+    /// All attributes that repeat the key of an earlier attribute
+    pub fn duplicates(&self) -> impl Iterator<Item = &Attribute<'a>> {
+        self.entries
+            .iter()
+            .enumerate()
+            .filter_map(move |(index, attribute)| {
+                self.entries[..index]
+                    .iter()
+                    .any(|previous| previous.key.eq_ignore_ascii_case(&attribute.key))
+                    .then_some(attribute)
+            })
+    }
+
+    /// Detaches the attributes from the parsed input, see
+    /// [`Dom::into_owned`](crate::Dom::into_owned)
+    pub fn into_owned(self) -> Attributes<'static> {
+        Attributes {
+            entries: self
+                .entries
+                .into_iter()
+                .map(Attribute::into_owned)
+                .collect(),
+        }
+    }
+}
+
+impl fmt::Debug for Attributes<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(&self.entries).finish()
+    }
+}
+
+impl<'a> Index<&str> for Attributes<'a> {
+    type Output = Option<Cow<'a, str>>;
+
+    /// Returns the value of the first attribute with the key
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no attribute with the key
+    fn index(&self, key: &str) -> &Self::Output {
+        self.get(key).expect("no attribute found for key")
+    }
+}
+
+impl<'b, 'a> IntoIterator for &'b Attributes<'a> {
+    type Item = &'b Attribute<'a>;
+    type IntoIter = Iter<'b, Attribute<'a>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a> IntoIterator for Attributes<'a> {
+    type Item = Attribute<'a>;
+    type IntoIter = std::vec::IntoIter<Attribute<'a>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+impl<'a, K, V> FromIterator<(K, Option<V>)> for Attributes<'a>
+where
+    K: Into<Cow<'a, str>>,
+    V: Into<Cow<'a, str>>,
+{
+    fn from_iter<T: IntoIterator<Item = (K, Option<V>)>>(iter: T) -> Self {
+        Self {
+            entries: iter
+                .into_iter()
+                .map(|(key, value)| Attribute::new(key, value.map(Into::into)))
+                .collect(),
+        }
+    }
+}
+
+/// Serialized as a map in source order, where only the first of any duplicates is kept
+impl Serialize for Attributes<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        for (index, attribute) in self.entries.iter().enumerate() {
+            if self.entries[..index]
+                .iter()
+                .all(|previous| !previous.key.eq_ignore_ascii_case(&attribute.key))
+            {
+                map.serialize_entry(&attribute.key, &attribute.value)?;
+            }
+        }
+        map.end()
+    }
+}
+
+/// Attributes are read from an object, as written by [`Dom::to_json`](crate::Dom::to_json), or
+/// from a list of objects with `key` and `value`, as written with the tagged json schema.
+impl<'de, 'a> Deserialize<'de> for Attributes<'a> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct AttributesVisitor<'a>(PhantomData<Attributes<'a>>);
+
+        impl<'de, 'a> Visitor<'de> for AttributesVisitor<'a> {
+            type Value = Attributes<'a>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a map or a list of attributes")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut attributes = Attributes::new();
+                while let Some((key, value)) = map.next_entry::<String, Option<String>>()? {
+                    attributes.push(Attribute::new(key, value.map(Cow::Owned)));
+                }
+                Ok(attributes)
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let mut attributes = Attributes::new();
+                while let Some(attribute) = seq.next_element()? {
+                    attributes.push(attribute);
+                }
+                Ok(attributes)
+            }
+        }
+
+        deserializer.deserialize_any(AttributesVisitor(PhantomData))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attributes_keep_order_and_duplicates() {
+        let mut attributes = Attributes::new();
+        attributes.push(Attribute::new("b", Some("1".into())));
+        attributes.push(Attribute::new("a", None));
+        attributes.push(Attribute::new("b", Some("2".into())));
+
+        let keys = attributes
+            .iter()
+            .map(|x| x.key.as_ref())
+            .collect::<Vec<_>>();
+        assert_eq!(keys, vec!["b", "a", "b"]);
+        assert_eq!(attributes["b"], Some("1".into()));
+        assert_eq!(attributes.get_all("b").count(), 2);
+        assert_eq!(attributes.duplicates().count(), 1);
+
+        assert_eq!(
+            attributes.insert("b", Some("3".into())),
+            Some(Some("1".into()))
+        );
+        assert_eq!(attributes.insert("c", None), None);
+        assert_eq!(attributes.len(), 4);
+
+        assert_eq!(attributes.remove("b"), Some(Some("3".into())));
+        assert_eq!(attributes.len(), 2);
+        assert!(!attributes.contains_key("b"));
+    }
+}
@@ -0,0 +1,1020 @@
+use super::element::{Element, ElementVariant};
+use super::events::{Event, RawEvent};
+use super::html;
+use super::node::{Comment, ConditionalComment, Node, Text};
+use super::options::DanglingEndTags;
+use super::span::SourceSpan;
+use super::{Context, Dom, DomVariant, Mode};
+use crate::error::{ParseError, ParseErrorKind, Severity};
+use crate::tokenizer::Span;
+use std::borrow::Cow;
+
+/// Elements whose end tag may be left out, so closing them without one isn't reported.
+/// See: https://html.spec.whatwg.org/multipage/syntax.html#optional-tags
+const OPTIONAL_END_TAGS: [&str; 19] = [
+    "html", "head", "body", "li", "dt", "dd", "p", "rt", "rp", "optgroup", "option", "colgroup",
+    "caption", "thead", "tbody", "tfoot", "tr", "td", "th",
+];
+
+/// Start tags that close an open `p`, ex: `<p>one<div>two</div>`.
+/// See: https://html.spec.whatwg.org/multipage/syntax.html#optional-tags
+const CLOSES_P: [&str; 38] = [
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "center",
+    "details",
+    "dialog",
+    "dir",
+    "div",
+    "dl",
+    "dd",
+    "dt",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hgroup",
+    "hr",
+    "li",
+    "listing",
+    "main",
+    "menu",
+    "nav",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "summary",
+    "table",
+    "ul",
+];
+
+/// Elements that may be in `head`, any other start tag closes it
+const HEAD_CONTENT: [&str; 9] = [
+    "base", "basefont", "bgsound", "link", "meta", "noscript", "script", "style", "title",
+];
+
+const HEADINGS: [&str; 6] = ["h1", "h2", "h3", "h4", "h5", "h6"];
+
+// The elements that stop the search for an open element, see
+// https://html.spec.whatwg.org/multipage/parsing.html#has-an-element-in-scope
+const DEFAULT_SCOPE: [&str; 9] = [
+    "applet", "caption", "html", "table", "td", "th", "marquee", "object", "template",
+];
+const BUTTON_SCOPE: [&str; 10] = [
+    "applet", "caption", "html", "table", "td", "th", "marquee", "object", "template", "button",
+];
+const LIST_ITEM_SCOPE: [&str; 11] = [
+    "applet", "caption", "html", "table", "td", "th", "marquee", "object", "template", "ol", "ul",
+];
+const DEFINITION_SCOPE: [&str; 10] = [
+    "applet", "caption", "html", "table", "td", "th", "marquee", "object", "template", "dl",
+];
+const TABLE_SCOPE: [&str; 3] = ["html", "table", "template"];
+
+/// Elements that are reopened when they are closed by another element, and that are closed with
+/// the adoption agency algorithm when they are misnested, ex: `<b><i>x</b>y</i>`.
+/// See: https://html.spec.whatwg.org/multipage/parsing.html#formatting
+const FORMATTING_ELEMENTS: [&str; 14] = [
+    "a", "b", "big", "code", "em", "font", "i", "nobr", "s", "small", "strike", "strong", "tt", "u",
+];
+
+/// The most formatting elements that are reopened at once, unlike in browsers. Otherwise hostile
+/// input, ex: `<p><b x=1>t` repeated with other attributes, reopens all of the earlier ones in
+/// every paragraph, which grows the tree quadratically.
+const MAX_REOPENED: usize = 16;
+
+/// The most entries that the list of active formatting elements keeps after the last marker,
+/// unlike in browsers. The oldest one is forgotten past it, so that it's closed by its end tag like
+/// other elements and isn't reopened. Otherwise hostile input, ex: `<b x=1>` repeated with other
+/// attributes, makes every formatting start tag compare itself with all of the earlier ones.
+const MAX_ACTIVE_FORMATTING: usize = 64;
+
+/// Elements that stop formatting elements from being reopened past them
+const FORMATTING_MARKERS: [&str; 7] = [
+    "applet", "caption", "marquee", "object", "template", "td", "th",
+];
+
+/// Elements that a misnested formatting element is moved into by the adoption agency algorithm,
+/// leaving out void elements, which are never open.
+/// See: https://html.spec.whatwg.org/multipage/parsing.html#special
+const SPECIAL_ELEMENTS: [&str; 65] = [
+    "address",
+    "applet",
+    "article",
+    "aside",
+    "blockquote",
+    "body",
+    "button",
+    "caption",
+    "center",
+    "colgroup",
+    "dd",
+    "details",
+    "dir",
+    "div",
+    "dl",
+    "dt",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "frameset",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "head",
+    "header",
+    "hgroup",
+    "html",
+    "iframe",
+    "li",
+    "listing",
+    "main",
+    "marquee",
+    "menu",
+    "nav",
+    "noembed",
+    "noframes",
+    "noscript",
+    "object",
+    "ol",
+    "p",
+    "plaintext",
+    "pre",
+    "script",
+    "section",
+    "select",
+    "style",
+    "summary",
+    "table",
+    "tbody",
+    "td",
+    "template",
+    "textarea",
+    "tfoot",
+    "th",
+    "thead",
+    "title",
+    "tr",
+    "ul",
+    "xmp",
+    "search",
+];
+
+/// Start tags that don't reopen formatting elements, as they aren't in the same paragraph
+const NOT_REOPENING: [&str; 10] = [
+    "html", "head", "body", "caption", "colgroup", "col", "tbody", "tfoot", "thead", "tr",
+];
+
+const TABLE_ELEMENTS: [&str; 8] = [
+    "caption", "table", "tbody", "td", "tfoot", "th", "thead", "tr",
+];
+
+fn is_one_of(name: &str, names: &[&str]) -> bool {
+    names.iter().any(|x| x.eq_ignore_ascii_case(name))
+}
+
+/// The elements that stop the search for the element that an end tag closes
+fn end_tag_scope(name: &str) -> &'static [&'static str] {
+    if name.eq_ignore_ascii_case("li") {
+        &LIST_ITEM_SCOPE
+    } else if name.eq_ignore_ascii_case("p") {
+        &BUTTON_SCOPE
+    } else if is_one_of(name, &TABLE_ELEMENTS) {
+        &TABLE_SCOPE
+    } else {
+        &DEFAULT_SCOPE
+    }
+}
+
+/// An element, or a conditional comment, that collects the nodes that follow it until it's closed
+struct Open<'i> {
+    /// Tells the open nodes apart, as a formatting element can be open more than once
+    id: usize,
+    node: Node<'i>,
+    /// The start tag of the element, or the start of the conditional comment
+    start: Span<'i>,
+    /// Whether the content is svg or math
+    foreign: bool,
+    /// Whether a marker was added to the list of active formatting elements when it was opened
+    marker: bool,
+}
+
+/// An entry in the list of active formatting elements
+// Elements are kept inline, as the list is short and markers are few
+#[allow(clippy::large_enum_variant)]
+enum Formatting<'i> {
+    /// The start of an element, or a conditional comment, whose content doesn't reopen the
+    /// formatting elements that come before it
+    Marker,
+    /// A formatting element, where the element is kept without children so that it can be
+    /// reopened
+    Element {
+        id: usize,
+        element: Element<'i>,
+        start: Span<'i>,
+    },
+}
+
+impl Formatting<'_> {
+    fn id(&self) -> Option<usize> {
+        match self {
+            Formatting::Marker => None,
+            Formatting::Element { id, .. } => Some(*id),
+        }
+    }
+}
+
+/// How an end tag was handled by the adoption agency algorithm
+enum Adoption {
+    /// The formatting element was closed
+    Closed,
+    /// The end tag is ignored, as the formatting element was already closed
+    Ignored,
+    /// There is no open formatting element with the name of the end tag
+    NotFormatting,
+}
+
+/// Builds the tree from the flat list of events of the input. Open elements are kept on a stack,
+/// and elements whose end tag is missing are closed as in browsers, ex: `<li>` closes an open
+/// `li`. Elements that are closed without an end tag that can't be left out are reported as
+/// warnings in [`Dom::errors`].
+///
+/// Misnested formatting elements, like `b` and `i`, are closed with the adoption agency algorithm
+/// and reopened where needed, ex: `<b><i>x</b>y</i>` becomes `<b><i>x</i></b><i>y</i>`, and are
+/// reported as warnings. Reopened elements are copies of the original element, including its
+/// start tag span. This follows the spec, except that formatting elements aren't reopened in
+/// tables that were opened after them, as tables keep their content in place here.
+///
+/// See: https://html.spec.whatwg.org/multipage/parsing.html#tree-construction
+pub(super) struct TreeBuilder<'i, 'c> {
+    input: &'i str,
+    context: &'c Context<'i>,
+    dom: Dom<'i>,
+    open: Vec<Open<'i>>,
+    formatting: Vec<Formatting<'i>>,
+    next_id: usize,
+    /// Whether a node was opened next to the innermost open node, as it was nested too deep
+    too_deep: bool,
+    /// The number of nodes that were created, see
+    /// [`ParseOptions::max_nodes`](super::options::ParseOptions::max_nodes)
+    nodes: usize,
+}
+
+impl<'i, 'c> TreeBuilder<'i, 'c> {
+    pub fn new(input: &'i str, context: &'c Context<'i>) -> Self {
+        Self {
+            input,
+            context,
+            dom: Dom::default(),
+            open: vec![],
+            formatting: vec![],
+            next_id: 0,
+            too_deep: false,
+            nodes: 0,
+        }
+    }
+
+    pub fn nodes(&self) -> usize {
+        self.nodes
+    }
+
+    /// Where the tokens are reported, ex: duplicate attributes in strict mode
+    pub fn errors(&mut self) -> &mut Vec<ParseError> {
+        &mut self.dom.errors
+    }
+
+    pub fn event(&mut self, event: RawEvent<'i>) {
+        let (event, span) = match event {
+            RawEvent::Event { event, span } => (event, span),
+            RawEvent::RawText {
+                start_tag,
+                start,
+                text,
+                content,
+                end_tag,
+            } => return self.raw_text_element(start_tag, start, text, content, end_tag),
+            RawEvent::BogusComment {
+                comment,
+                span,
+                unclosed,
+            } => return self.bogus_comment(comment, span, unclosed),
+        };
+        let context = self.context;
+        match event {
+            Event::Doctype(doctype) => {
+                if self.open.is_empty() && self.dom.tree_type == DomVariant::Empty {
+                    self.dom.tree_type = DomVariant::Document;
+                }
+                self.insert(Node::Doctype(doctype));
+            }
+            Event::Text(text) => {
+                self.content_at_root();
+                if Dom::keep_text(span.as_str(), context) {
+                    self.reopen_formatting();
+                    self.insert(Node::Text(text));
+                }
+            }
+            Event::Comment(comment) => {
+                if context.options.keep_comments {
+                    self.insert(Node::Comment(comment));
+                }
+            }
+            Event::Cdata(cdata) => {
+                self.content_at_root();
+                let node = Dom::build_cdata(cdata, self.in_foreign_content(), context);
+                self.insert(node);
+            }
+            Event::ProcessingInstruction(instruction) => {
+                self.insert(Node::ProcessingInstruction(instruction));
+            }
+            Event::ConditionalCommentStart {
+                condition,
+                revealed,
+                ..
+            } => self.conditional_comment_start(span, condition, revealed),
+            Event::ConditionalCommentEnd { .. } => self.conditional_comment_end(span),
+            Event::StartTag {
+                name,
+                attributes,
+                self_closing,
+                span: source_span,
+            } => {
+                let element = Dom::build_element(name, attributes, source_span, context);
+                self.start_tag(element, span, self_closing);
+            }
+            Event::EndTag { name, .. } => self.end_tag(span, &name),
+        }
+    }
+
+    /// Closes the nodes that are still open at the end of the input, and returns the tree
+    pub fn finish(mut self) -> Dom<'i> {
+        let end = self.input.len();
+        while !self.open.is_empty() {
+            self.close_implied(end);
+        }
+        self.dom
+    }
+
+    /// Markup that can't be parsed, in lenient mode, is kept as a comment
+    fn bogus_comment(&mut self, comment: Comment<'i>, span: Span<'i>, unclosed: bool) {
+        let span = span.trim();
+        let message = match unclosed {
+            true => "Unclosed comment",
+            false => "Bogus comment",
+        };
+        self.dom.errors.push(ParseError {
+            severity: Severity::Warning,
+            ..ParseError::new(
+                ParseErrorKind::Syntax,
+                message,
+                self.context.error_span(span),
+            )
+        });
+        if self.context.options.keep_comments {
+            self.insert(Node::Comment(comment));
+        }
+    }
+
+    /// Adds an element, where `start` is its start tag
+    fn start_tag(&mut self, element: Element<'i>, start: Span<'i>, self_closing: bool) {
+        let in_foreign_content = self.in_foreign_content();
+        let formatting = !in_foreign_content && is_one_of(&element.name, &FORMATTING_ELEMENTS);
+        if !in_foreign_content {
+            self.close_implied_by(&element.name, start);
+            if !is_one_of(&element.name, &CLOSES_P) && !is_one_of(&element.name, &NOT_REOPENING) {
+                self.reopen_formatting();
+            }
+        }
+        self.content_at_root();
+
+        let void = html::is_void_element(&element.name);
+        if self_closing && !void && !in_foreign_content && self.context.mode == Mode::Strict {
+            self.dom.errors.push(ParseError {
+                severity: Severity::Warning,
+                ..ParseError::new(
+                    ParseErrorKind::SelfClosingNonVoidElement,
+                    format!("Self-closing non-void element: {}", start.as_str().trim()),
+                    self.context.error_span(start.trim()),
+                )
+            });
+        }
+        if self_closing || void {
+            self.insert(Node::Element(element));
+            return;
+        }
+        if formatting {
+            self.push_formatting(&element, start);
+        }
+        let foreign = html::has_foreign_content(&element.name, in_foreign_content);
+        let marker = !in_foreign_content && is_one_of(&element.name, &FORMATTING_MARKERS);
+        self.push(Node::Element(element), start, foreign);
+        if marker {
+            self.push_marker();
+        }
+    }
+
+    /// Raw text elements are a single token, with the start tag, the content and the end tag
+    fn raw_text_element(
+        &mut self,
+        start_tag: Event<'i>,
+        start: Span<'i>,
+        text: Text<'i>,
+        content: Span<'i>,
+        end_tag: Option<(Event<'i>, Span<'i>)>,
+    ) {
+        let element = match start_tag {
+            Event::StartTag {
+                name,
+                attributes,
+                span,
+                ..
+            } => Dom::build_element(name, attributes, span, self.context),
+            event => unreachable!("[build tree] raw text start tag: {:?}", event),
+        };
+        let in_foreign_content = self.in_foreign_content();
+        if !in_foreign_content {
+            self.close_implied_by(&element.name, start);
+        }
+        self.content_at_root();
+
+        self.push(Node::Element(element), start, in_foreign_content);
+        if Dom::keep_text(content.as_str(), self.context) {
+            self.insert(Node::Text(text));
+        }
+        match end_tag {
+            Some((_, end_tag)) => self.close(end_tag.start(), Some(end_tag)),
+            None => self.close_implied(content.end()),
+        }
+    }
+
+    fn end_tag(&mut self, span: Span<'i>, name: &str) {
+        if !self.in_foreign_content() && is_one_of(name, &FORMATTING_ELEMENTS) {
+            match self.adoption_agency(name, span, Some(span)) {
+                Adoption::Closed => return,
+                Adoption::Ignored => return self.dangling_end_tag(span),
+                Adoption::NotFormatting => (),
+            }
+        }
+        match self.find_in_scope(&[name], end_tag_scope(name)) {
+            Some(index) => self.close_up_to(index, span.start(), Some(span)),
+            None => self.dangling_end_tag(span),
+        }
+    }
+
+    fn conditional_comment_start(
+        &mut self,
+        span: Span<'i>,
+        condition: Cow<'i, str>,
+        revealed: bool,
+    ) {
+        let start = span.trim();
+        let comment = ConditionalComment {
+            condition,
+            downlevel_revealed: revealed,
+            ..ConditionalComment::default()
+        };
+        let foreign = self.in_foreign_content();
+        self.push(Node::ConditionalComment(comment), start, foreign);
+        self.push_marker();
+    }
+
+    /// Closes the innermost conditional comment, and the elements that were opened in it
+    fn conditional_comment_end(&mut self, token: Span<'i>) {
+        let span = token.trim();
+        let index = self
+            .open
+            .iter()
+            .rposition(|open| matches!(open.node, Node::ConditionalComment(_)));
+        match index {
+            Some(index) => self.close_up_to(index, span.start(), Some(span)),
+            None => self.dangling_end_tag(token),
+        }
+    }
+
+    /// The end tag, or the end of a conditional comment, with the whitespace after it
+    fn dangling_end_tag(&mut self, token: Span<'i>) {
+        match self.context.options.dangling_end_tags {
+            DanglingEndTags::Discard if self.context.mode != Mode::Strict => (),
+            DanglingEndTags::Discard | DanglingEndTags::Report => {
+                self.dom.errors.push(ParseError::new(
+                    ParseErrorKind::DanglingEndTag,
+                    format!("Dangling end tag: {}", token.as_str().trim()),
+                    self.context.error_span(token.trim()),
+                ))
+            }
+            DanglingEndTags::KeepAsText => {
+                self.content_at_root();
+                self.insert(Node::Text(Dom::build_text(token, false, self.context)));
+            }
+        }
+    }
+
+    /// Closes the elements whose end tag is implied by the start tag of `name`, ex: `<li>` closes
+    /// an open `li` and `<div>` closes an open `p`
+    fn close_implied_by(&mut self, name: &str, start_tag: Span<'i>) {
+        let position = start_tag.start();
+        if self.current_is(&["head"]) && !is_one_of(name, &HEAD_CONTENT) {
+            self.close(position, None);
+        }
+        if is_one_of(name, &CLOSES_P) {
+            self.close_in_scope(&["p"], &BUTTON_SCOPE, position);
+        }
+        match name.to_ascii_lowercase().as_str() {
+            "li" => self.close_in_scope(&["li"], &LIST_ITEM_SCOPE, position),
+            "dt" | "dd" => self.close_in_scope(&["dt", "dd"], &DEFINITION_SCOPE, position),
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" if self.current_is(&HEADINGS) => {
+                self.close_implied(position)
+            }
+            "option" if self.current_is(&["option"]) => self.close(position, None),
+            "optgroup" => {
+                if self.current_is(&["option"]) {
+                    self.close(position, None);
+                }
+                if self.current_is(&["optgroup"]) {
+                    self.close(position, None);
+                }
+            }
+            "tr" => {
+                self.close_in_scope(&["tr"], &TABLE_SCOPE, position);
+                self.close_in_scope(&["td", "th"], &TABLE_SCOPE, position);
+            }
+            "td" | "th" => self.close_in_scope(&["td", "th"], &TABLE_SCOPE, position),
+            "thead" | "tbody" | "tfoot" => {
+                self.close_in_scope(&["thead", "tbody", "tfoot"], &TABLE_SCOPE, position)
+            }
+            "rt" | "rp" if self.current_is(&["rt", "rp"]) => self.close(position, None),
+            "body" => self.close_in_scope(&["head"], &["html"], position),
+            "button" => self.close_in_scope(&["button"], &DEFAULT_SCOPE, position),
+            // A link can't be in another link, so the open one is closed as if it's misnested
+            "a" | "nobr" if self.find_formatting(name).is_some() => {
+                self.adoption_agency(name, start_tag, None);
+            }
+            _ => (),
+        }
+    }
+
+    /// Opens an element, or a conditional comment
+    fn push(&mut self, node: Node<'i>, start: Span<'i>, foreign: bool) {
+        self.limit_depth(start);
+        self.nodes += 1;
+        let id = self.next_id;
+        self.next_id += 1;
+        self.open.push(Open {
+            id,
+            node,
+            start,
+            foreign,
+            marker: false,
+        });
+    }
+
+    /// Stops the formatting elements that are active from being reopened in the current node
+    fn push_marker(&mut self) {
+        self.formatting.push(Formatting::Marker);
+        if let Some(open) = self.open.last_mut() {
+            open.marker = true;
+        }
+    }
+
+    /// Adds a formatting element that is about to be opened to the list of active formatting
+    /// elements. The list keeps at most three elements with the same name and attributes, and at
+    /// most [`MAX_ACTIVE_FORMATTING`] after the last marker.
+    fn push_formatting(&mut self, element: &Element<'i>, start: Span<'i>) {
+        // The attributes are compared without their spans, which are never the same
+        let same_attributes = |other: &Element| {
+            other.attributes.len() == element.attributes.len()
+                && other
+                    .attributes
+                    .iter()
+                    .zip(&element.attributes)
+                    .all(|(a, b)| a.key.eq_ignore_ascii_case(&b.key) && a.value == b.value)
+        };
+        let same = |entry: &Formatting| match entry {
+            Formatting::Element { element: other, .. } => {
+                other.name.eq_ignore_ascii_case(&element.name)
+                    && other.id == element.id
+                    && other.classes == element.classes
+                    && same_attributes(other)
+            }
+            Formatting::Marker => false,
+        };
+        let after_marker = self
+            .formatting
+            .iter()
+            .rposition(|entry| matches!(entry, Formatting::Marker))
+            .map_or(0, |index| index + 1);
+        let mut same_indices =
+            (after_marker..self.formatting.len()).filter(|&index| same(&self.formatting[index]));
+        if let (Some(first), 3) = (same_indices.next(), same_indices.count() + 1) {
+            self.formatting.remove(first);
+        }
+        if self.formatting.len() - after_marker >= MAX_ACTIVE_FORMATTING {
+            self.formatting.remove(after_marker);
+        }
+        self.formatting.push(Formatting::Element {
+            id: self.next_id,
+            element: element.clone(),
+            start,
+        });
+    }
+
+    /// The index in the list of active formatting elements of the last element with the name,
+    /// after the last marker
+    fn find_formatting(&self, name: &str) -> Option<usize> {
+        for (index, entry) in self.formatting.iter().enumerate().rev() {
+            match entry {
+                Formatting::Marker => return None,
+                Formatting::Element { element, .. } if element.name.eq_ignore_ascii_case(name) => {
+                    return Some(index)
+                }
+                Formatting::Element { .. } => (),
+            }
+        }
+        None
+    }
+
+    fn is_open(&self, id: usize) -> bool {
+        self.open.iter().any(|open| open.id == id)
+    }
+
+    /// Opens a copy of a formatting element, and points its entry in the list of active
+    /// formatting elements at the copy
+    fn reopen(&mut self, index: usize) -> Open<'i> {
+        self.nodes += 1;
+        let id = self.next_id;
+        self.next_id += 1;
+        match &mut self.formatting[index] {
+            Formatting::Element {
+                id: entry_id,
+                element,
+                start,
+            } => {
+                *entry_id = id;
+                Open {
+                    id,
+                    node: Node::Element(element.clone()),
+                    start: *start,
+                    foreign: false,
+                    marker: false,
+                }
+            }
+            Formatting::Marker => unreachable!("[build tree] a marker can't be reopened"),
+        }
+    }
+
+    /// Reopens the formatting elements that were closed by another element, so that they apply
+    /// to the content that follows, ex: the `b` in `<p><b>one</p>two`
+    fn reopen_formatting(&mut self) {
+        if self.in_foreign_content() {
+            return;
+        }
+        let mut index = self.formatting.len();
+        while index > 0 {
+            match self.formatting[index - 1].id() {
+                Some(id) if !self.is_open(id) => index -= 1,
+                _ => break,
+            }
+        }
+        // The outermost ones are forgotten if there are too many
+        if self.formatting.len() - index > MAX_REOPENED {
+            self.formatting
+                .drain(index..self.formatting.len() - MAX_REOPENED);
+        }
+        for index in index..self.formatting.len() {
+            let open = self.reopen(index);
+            self.limit_depth(open.start);
+            self.open.push(open);
+        }
+    }
+
+    /// Makes room for a node that is about to be opened, if the open nodes are as deep as
+    /// [`ParseOptions::max_depth`](super::options::ParseOptions::max_depth), by closing the
+    /// innermost one. The node is then opened next to it instead of inside of it, as in browsers.
+    /// Only the first time is reported, as hostile input may be nested thousands of times.
+    fn limit_depth(&mut self, start: Span<'i>) {
+        if self.open.is_empty() || self.open.len() < self.context.options.max_depth {
+            return;
+        }
+        if !self.too_deep {
+            self.too_deep = true;
+            self.dom.errors.push(ParseError {
+                severity: Severity::Warning,
+                ..ParseError::new(
+                    ParseErrorKind::NestingTooDeep,
+                    format!(
+                        "Nesting too deep: more than {} open elements",
+                        self.context.options.max_depth
+                    ),
+                    self.context.error_span(start.trim()),
+                )
+            });
+        }
+        self.close(start.start(), None);
+    }
+
+    /// Closes a formatting element, where elements that were opened inside of it and that can't
+    /// be in a formatting element, like `p`, are moved out of it and get a copy of it instead.
+    /// `position` is where the element is closed, and `end_tag` is its end tag if it has one.
+    ///
+    /// See: https://html.spec.whatwg.org/multipage/parsing.html#adoption-agency-algorithm
+    fn adoption_agency(
+        &mut self,
+        name: &str,
+        tag: Span<'i>,
+        end_tag: Option<Span<'i>>,
+    ) -> Adoption {
+        let position = tag.start();
+        if let Some(open) = self.open.last() {
+            let is_current = match &open.node {
+                Node::Element(element) => element.name.eq_ignore_ascii_case(name),
+                _ => false,
+            };
+            if is_current
+                && !self
+                    .formatting
+                    .iter()
+                    .any(|entry| entry.id() == Some(open.id))
+            {
+                self.close(position, end_tag);
+                return Adoption::Closed;
+            }
+        }
+
+        for round in 0..8 {
+            let index = match self.find_formatting(name) {
+                Some(index) => index,
+                None if round == 0 => return Adoption::NotFormatting,
+                None => return Adoption::Closed,
+            };
+            let id = self.formatting[index].id().expect("a formatting element");
+            let stack_index = match self.open.iter().position(|open| open.id == id) {
+                Some(stack_index) => stack_index,
+                None => {
+                    self.formatting.remove(index);
+                    return Adoption::Ignored;
+                }
+            };
+            if !self.in_scope(stack_index) {
+                return Adoption::Ignored;
+            }
+            if round == 0 && stack_index + 1 != self.open.len() {
+                let message = match end_tag {
+                    Some(end_tag) => format!("Misnested end tag: {}", end_tag.as_str()),
+                    None => format!("Misnested start tag: {}", tag.as_str()),
+                };
+                self.dom.errors.push(ParseError {
+                    severity: Severity::Warning,
+                    ..ParseError::new(
+                        ParseErrorKind::MisnestedElement,
+                        message,
+                        self.context.error_span(tag),
+                    )
+                });
+            }
+
+            // Without an element that has to be moved out, the formatting element is closed with
+            // everything in it
+            let furthest_block = (stack_index + 1..self.open.len()).find(|&index| {
+                let name = self.open[index].node.element().map(|element| &element.name);
+                name.is_some_and(|name| is_one_of(name, &SPECIAL_ELEMENTS))
+            });
+            let furthest_block = match furthest_block {
+                Some(furthest_block) => furthest_block,
+                None => {
+                    while self.open.len() > stack_index + 1 {
+                        self.close(position, None);
+                    }
+                    self.close(position, end_tag);
+                    self.formatting.remove(index);
+                    return Adoption::Closed;
+                }
+            };
+
+            // The elements between the formatting element and the furthest block are closed,
+            // and the formatting elements among them are reopened around the furthest block
+            let above = self.open.split_off(furthest_block + 1);
+            let mut block = self.open.pop().expect("the furthest block");
+            let mut reopened = vec![];
+            // Where the copy of the formatting element goes in the list, after the copy of the
+            // element around the furthest block, or in place of the formatting element
+            let mut bookmark = None;
+            let mut counter = 0;
+            while self.open.len() > stack_index + 1 {
+                counter += 1;
+                let node_id = self.open.last().expect("an open node").id;
+                let mut entry = self.formatting.iter().position(|x| x.id() == Some(node_id));
+                if let (true, Some(entry_index)) = (counter > 3, entry) {
+                    self.formatting.remove(entry_index);
+                    entry = None;
+                }
+                if let Some(entry_index) = entry {
+                    let open = self.reopen(entry_index);
+                    if reopened.is_empty() {
+                        bookmark = Some(open.id);
+                    }
+                    reopened.push(open);
+                }
+                self.close(position, None);
+            }
+            // The formatting element itself
+            self.close(position, None);
+
+            let index = self
+                .formatting
+                .iter()
+                .position(|x| x.id() == Some(id))
+                .expect("the formatting element");
+            let mut copy = self.reopen(index);
+            if let (Some(element), Some(children)) =
+                (copy.node.element_mut(), block.node.children_mut())
+            {
+                element.children = std::mem::take(children);
+            }
+            if let Some(bookmark) = bookmark {
+                let entry = self.formatting.remove(index);
+                let after = self
+                    .formatting
+                    .iter()
+                    .position(|x| x.id() == Some(bookmark))
+                    .map_or(self.formatting.len(), |index| index + 1);
+                self.formatting.insert(after, entry);
+            }
+            self.open.extend(reopened.into_iter().rev());
+            self.open.push(block);
+            self.open.push(copy);
+            self.open.extend(above);
+        }
+        Adoption::Closed
+    }
+
+    /// Whether the open node at the index isn't hidden by an element of the default scope, or by
+    /// a conditional comment
+    fn in_scope(&self, stack_index: usize) -> bool {
+        self.open[stack_index + 1..]
+            .iter()
+            .all(|open| match &open.node {
+                Node::Element(element) => !is_one_of(&element.name, &DEFAULT_SCOPE),
+                _ => false,
+            })
+    }
+
+    fn close_in_scope(&mut self, names: &[&str], scope: &[&str], position: usize) {
+        if let Some(index) = self.find_in_scope(names, scope) {
+            self.close_up_to(index, position, None);
+        }
+    }
+
+    /// The index of the innermost open element with one of the names, unless an element of the
+    /// scope, or a conditional comment, comes first
+    fn find_in_scope(&self, names: &[&str], scope: &[&str]) -> Option<usize> {
+        for (index, open) in self.open.iter().enumerate().rev() {
+            let element = match &open.node {
+                Node::Element(element) => element,
+                _ => return None,
+            };
+            if is_one_of(&element.name, names) {
+                return Some(index);
+            }
+            if is_one_of(&element.name, scope) {
+                return None;
+            }
+        }
+        None
+    }
+
+    fn current_is(&self, names: &[&str]) -> bool {
+        match self.open.last().map(|open| &open.node) {
+            Some(Node::Element(element)) => is_one_of(&element.name, names),
+            _ => false,
+        }
+    }
+
+    fn in_foreign_content(&self) -> bool {
+        self.open.last().is_some_and(|open| open.foreign)
+    }
+
+    /// Anything but comments at the root makes the tree a fragment, if it isn't a document
+    fn content_at_root(&mut self) {
+        if self.open.is_empty() && self.dom.tree_type == DomVariant::Empty {
+            self.dom.tree_type = DomVariant::DocumentFragment;
+        }
+    }
+
+    /// Adds a new node to the current node
+    fn insert(&mut self, node: Node<'i>) {
+        self.nodes += 1;
+        self.append(node);
+    }
+
+    fn append(&mut self, node: Node<'i>) {
+        match self.open.last_mut() {
+            Some(open) => open
+                .node
+                .children_mut()
+                .expect("open nodes have children")
+                .push(node),
+            None => self.dom.children.push(node),
+        }
+    }
+
+    /// Closes the open nodes after `index` without an end tag, and then the node at `index`
+    fn close_up_to(&mut self, index: usize, position: usize, end_tag: Option<Span<'i>>) {
+        while self.open.len() > index + 1 {
+            self.close_implied(position);
+        }
+        self.close(position, end_tag);
+    }
+
+    /// Closes the current node without an end tag, and reports it if the end tag can't be left
+    /// out
+    fn close_implied(&mut self, position: usize) {
+        let open = self.open.last().expect("an open node");
+        let message = match &open.node {
+            Node::Element(element) if is_one_of(&element.name, &OPTIONAL_END_TAGS) => None,
+            Node::Element(element) => Some(format!("Unclosed element: <{}>", element.name)),
+            _ => Some(String::from("Unclosed conditional comment")),
+        };
+        if let Some(message) = message {
+            self.dom.errors.push(ParseError {
+                severity: Severity::Warning,
+                ..ParseError::new(
+                    ParseErrorKind::UnclosedElement,
+                    message,
+                    self.context.error_span(open.start),
+                )
+            });
+        }
+        self.close(position, None);
+    }
+
+    /// Closes the current node, where the content ends at the end tag or, without an end tag, at
+    /// `position` without the whitespace before it
+    fn close(&mut self, position: usize, end_tag: Option<Span<'i>>) {
+        let open = self.open.pop().expect("an open node");
+        let content_end = match end_tag {
+            Some(end_tag) => end_tag.start(),
+            None => self.input[..position]
+                .trim_end()
+                .len()
+                .max(open.start.end()),
+        };
+        let end = end_tag.map_or(content_end, |end_tag| end_tag.end());
+        let source_span = self.span(open.start.start(), end);
+
+        // Formatting elements that were opened in the node aren't reopened after it
+        if open.marker {
+            if let Some(index) = self
+                .formatting
+                .iter()
+                .rposition(|entry| matches!(entry, Formatting::Marker))
+            {
+                self.formatting.truncate(index);
+            }
+        }
+        let node = match open.node {
+            Node::Element(mut element) => {
+                element.variant = ElementVariant::Normal;
+                element.source_span = source_span;
+                element.end_tag_span =
+                    end_tag.map(|end_tag| Dom::build_source_span(end_tag, self.context));
+                element.content_span = Some(self.span(open.start.end(), content_end));
+                Node::Element(element)
+            }
+            Node::ConditionalComment(mut comment) => {
+                // Without comments, the content that every browser shows is kept in the parent
+                if !self.context.options.keep_comments {
+                    if comment.downlevel_revealed {
+                        for child in comment.children {
+                            self.append(child);
+                        }
+                    }
+                    return;
+                }
+                comment.source_span = source_span;
+                Node::ConditionalComment(comment)
+            }
+            node => unreachable!("[build tree] node can't be open: {:?}", node),
+        };
+        self.append(node);
+    }
+
+    fn span(&self, start: usize, end: usize) -> SourceSpan {
+        Dom::build_source_span(Span::new(self.input, start, end), self.context)
+    }
+}
@@ -0,0 +1,312 @@
+use super::span::SourceSpan;
+use serde::Deserialize;
+use std::fmt;
+
+/// A document type declaration, ex: `<!DOCTYPE html>`
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Doctype {
+    /// The name in lowercase, ex: `html`. `None` for `<!DOCTYPE>`
+    #[serde(default)]
+    pub name: Option<String>,
+
+    /// The public identifier, ex: `-//W3C//DTD HTML 4.01//EN`
+    #[serde(default)]
+    pub public_id: Option<String>,
+
+    /// The system identifier, ex: `http://www.w3.org/TR/html4/strict.dtd`
+    #[serde(default)]
+    pub system_id: Option<String>,
+
+    /// Set if the doctype is malformed, which puts the document in quirks mode
+    #[serde(default)]
+    pub force_quirks: bool,
+
+    /// Span of the doctype in the parsed source
+    #[serde(default)]
+    pub source_span: SourceSpan,
+}
+
+/// The mode that browsers render a document in, which is selected by its doctype.
+/// See <https://html.spec.whatwg.org/multipage/parsing.html#the-initial-insertion-mode>
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuirksMode {
+    /// Standards mode, ex: `<!DOCTYPE html>`
+    NoQuirks,
+    /// Almost standards mode, used by the transitional and frameset doctypes of xhtml 1.0
+    LimitedQuirks,
+    /// Emulates the behaviour of old browsers, used by old or missing doctypes
+    Quirks,
+}
+
+// Public identifiers that start with one of these put the document in quirks mode
+const QUIRKS_PUBLIC_ID_PREFIXES: &[&str] = &[
+    "+//Silmaril//dtd html Pro v0r11 19970101//",
+    "-//AS//DTD HTML 3.0 asWedit + extensions//",
+    "-//AdvaSoft Ltd//DTD HTML 3.0 asWedit + extensions//",
+    "-//IETF//DTD HTML 2.0 Level 1//",
+    "-//IETF//DTD HTML 2.0 Level 2//",
+    "-//IETF//DTD HTML 2.0 Strict Level 1//",
+    "-//IETF//DTD HTML 2.0 Strict Level 2//",
+    "-//IETF//DTD HTML 2.0 Strict//",
+    "-//IETF//DTD HTML 2.0//",
+    "-//IETF//DTD HTML 2.1E//",
+    "-//IETF//DTD HTML 3.0//",
+    "-//IETF//DTD HTML 3.2 Final//",
+    "-//IETF//DTD HTML 3.2//",
+    "-//IETF//DTD HTML 3//",
+    "-//IETF//DTD HTML Level 0//",
+    "-//IETF//DTD HTML Level 1//",
+    "-//IETF//DTD HTML Level 2//",
+    "-//IETF//DTD HTML Level 3//",
+    "-//IETF//DTD HTML Strict Level 0//",
+    "-//IETF//DTD HTML Strict Level 1//",
+    "-//IETF//DTD HTML Strict Level 2//",
+    "-//IETF//DTD HTML Strict Level 3//",
+    "-//IETF//DTD HTML Strict//",
+    "-//IETF//DTD HTML//",
+    "-//Metrius//DTD Metrius Presentational//",
+    "-//Microsoft//DTD Internet Explorer 2.0 HTML Strict//",
+    "-//Microsoft//DTD Internet Explorer 2.0 HTML//",
+    "-//Microsoft//DTD Internet Explorer 2.0 Tables//",
+    "-//Microsoft//DTD Internet Explorer 3.0 HTML Strict//",
+    "-//Microsoft//DTD Internet Explorer 3.0 HTML//",
+    "-//Microsoft//DTD Internet Explorer 3.0 Tables//",
+    "-//Netscape Comm. Corp.//DTD HTML//",
+    "-//Netscape Comm. Corp.//DTD Strict HTML//",
+    "-//O'Reilly and Associates//DTD HTML 2.0//",
+    "-//O'Reilly and Associates//DTD HTML Extended 1.0//",
+    "-//O'Reilly and Associates//DTD HTML Extended Relaxed 1.0//",
+    "-//SQ//DTD HTML 2.0 HoTMetaL + extensions//",
+    "-//SoftQuad Software//DTD HoTMetaL PRO 6.0::19990601::extensions to HTML 4.0//",
+    "-//SoftQuad//DTD HoTMetaL PRO 4.0::19971010::extensions to HTML 4.0//",
+    "-//Spyglass//DTD HTML 2.0 Extended//",
+    "-//Sun Microsystems Corp.//DTD HotJava HTML//",
+    "-//Sun Microsystems Corp.//DTD HotJava Strict HTML//",
+    "-//W3C//DTD HTML 3 1995-03-24//",
+    "-//W3C//DTD HTML 3.2 Draft//",
+    "-//W3C//DTD HTML 3.2 Final//",
+    "-//W3C//DTD HTML 3.2//",
+    "-//W3C//DTD HTML 3.2S Draft//",
+    "-//W3C//DTD HTML 4.0 Frameset//",
+    "-//W3C//DTD HTML 4.0 Transitional//",
+    "-//W3C//DTD HTML Experimental 19960712//",
+    "-//W3C//DTD HTML Experimental 970421//",
+    "-//W3C//DTD W3 HTML//",
+    "-//W3O//DTD W3 HTML 3.0//",
+    "-//WebTechs//DTD Mozilla HTML 2.0//",
+    "-//WebTechs//DTD Mozilla HTML//",
+];
+
+const QUIRKS_PUBLIC_IDS: &[&str] = &[
+    "-//W3O//DTD W3 HTML Strict 3.0//EN//",
+    "-/W3C/DTD HTML 4.0 Transitional/EN",
+    "HTML",
+];
+
+const QUIRKS_SYSTEM_ID: &str = "http://www.ibm.com/data/dtd/v11/ibmxhtml1-transitional.dtd";
+
+// Quirks mode without a system identifier, limited quirks mode with one
+const HTML4_TRANSITIONAL_PUBLIC_ID_PREFIXES: &[&str] = &[
+    "-//W3C//DTD HTML 4.01 Frameset//",
+    "-//W3C//DTD HTML 4.01 Transitional//",
+];
+
+const LIMITED_QUIRKS_PUBLIC_ID_PREFIXES: &[&str] = &[
+    "-//W3C//DTD XHTML 1.0 Frameset//",
+    "-//W3C//DTD XHTML 1.0 Transitional//",
+];
+
+impl Doctype {
+    /// Parses the content of a doctype, everything between `<!DOCTYPE` and `>`, as described in
+    /// <https://html.spec.whatwg.org/multipage/parsing.html#doctype-state>
+    pub(crate) fn parse(content: &str) -> Self {
+        let mut doctype = Doctype::default();
+
+        let rest = content.trim_start_matches(is_whitespace);
+        if rest.is_empty() {
+            doctype.force_quirks = true;
+            return doctype;
+        }
+        let name_end = rest.find(is_whitespace).unwrap_or(rest.len());
+        doctype.name = Some(rest[..name_end].to_ascii_lowercase());
+
+        let rest = rest[name_end..].trim_start_matches(is_whitespace);
+        if rest.is_empty() {
+            return doctype;
+        }
+        let keyword = rest.get(..6).unwrap_or_default();
+        let is_public = keyword.eq_ignore_ascii_case("public");
+        if !is_public && !keyword.eq_ignore_ascii_case("system") {
+            doctype.force_quirks = true;
+            return doctype;
+        }
+
+        let (id, rest) = match parse_identifier(&rest[6..]) {
+            Some(identifier) => identifier,
+            None => {
+                doctype.force_quirks = true;
+                return doctype;
+            }
+        };
+        if !is_public {
+            doctype.system_id = Some(id.to_string());
+            return doctype;
+        }
+        doctype.public_id = Some(id.to_string());
+
+        // The system identifier is optional after the public identifier
+        if rest.trim_start_matches(is_whitespace).is_empty() {
+            return doctype;
+        }
+        match parse_identifier(rest) {
+            Some((id, _)) => doctype.system_id = Some(id.to_string()),
+            None => doctype.force_quirks = true,
+        }
+        doctype
+    }
+
+    /// The quirks mode of a document with this doctype
+    pub fn quirks_mode(&self) -> QuirksMode {
+        let public_id = self.public_id.as_deref();
+        let system_id = self.system_id.as_deref();
+        let public_id_starts_with =
+            |prefixes: &[&str]| public_id.is_some_and(|id| starts_with_any(id, prefixes));
+
+        if self.force_quirks
+            || self.name.as_deref() != Some("html")
+            || public_id.is_some_and(|id| {
+                QUIRKS_PUBLIC_IDS
+                    .iter()
+                    .any(|quirks_id| id.eq_ignore_ascii_case(quirks_id))
+            })
+            || system_id.is_some_and(|id| id.eq_ignore_ascii_case(QUIRKS_SYSTEM_ID))
+            || public_id_starts_with(QUIRKS_PUBLIC_ID_PREFIXES)
+            || (system_id.is_none() && public_id_starts_with(HTML4_TRANSITIONAL_PUBLIC_ID_PREFIXES))
+        {
+            QuirksMode::Quirks
+        } else if public_id_starts_with(LIMITED_QUIRKS_PUBLIC_ID_PREFIXES)
+            || (system_id.is_some() && public_id_starts_with(HTML4_TRANSITIONAL_PUBLIC_ID_PREFIXES))
+        {
+            QuirksMode::LimitedQuirks
+        } else {
+            QuirksMode::NoQuirks
+        }
+    }
+}
+
+impl fmt::Display for Doctype {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("<!DOCTYPE")?;
+        if let Some(name) = &self.name {
+            write!(f, " {}", name)?;
+        }
+        match (&self.public_id, &self.system_id) {
+            (Some(public_id), system_id) => {
+                write!(f, " PUBLIC {}", Quoted(public_id))?;
+                if let Some(system_id) = system_id {
+                    write!(f, " {}", Quoted(system_id))?;
+                }
+            }
+            (None, Some(system_id)) => write!(f, " SYSTEM {}", Quoted(system_id))?,
+            (None, None) => (),
+        }
+        f.write_str(">")
+    }
+}
+
+/// An identifier in double quotes, or in single quotes if it contains a double quote
+struct Quoted<'a>(&'a str);
+
+impl fmt::Display for Quoted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let quote = if self.0.contains('"') { '\'' } else { '"' };
+        write!(f, "{}{}{}", quote, self.0, quote)
+    }
+}
+
+fn is_whitespace(c: char) -> bool {
+    c.is_ascii_whitespace()
+}
+
+fn starts_with_any(id: &str, prefixes: &[&str]) -> bool {
+    prefixes.iter().any(|prefix| {
+        id.get(..prefix.len())
+            .is_some_and(|start| start.eq_ignore_ascii_case(prefix))
+    })
+}
+
+/// Parses a quoted identifier after optional whitespace, and returns it with the rest of the
+/// input. `None` if there is no quote, or if the closing quote is missing.
+fn parse_identifier(input: &str) -> Option<(&str, &str)> {
+    let input = input.trim_start_matches(is_whitespace);
+    let quote = input.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+    let input = &input[1..];
+    let end = input.find(quote)?;
+    Some((&input[..end], &input[end + 1..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_the_name_and_identifiers() {
+        let doctype = Doctype::parse(
+            " HTML PUBLIC \"-//W3C//DTD HTML 4.01//EN\"\n 'http://www.w3.org/TR/html4/strict.dtd'",
+        );
+        assert_eq!(doctype.name.as_deref(), Some("html"));
+        assert_eq!(
+            doctype.public_id.as_deref(),
+            Some("-//W3C//DTD HTML 4.01//EN")
+        );
+        assert_eq!(
+            doctype.system_id.as_deref(),
+            Some("http://www.w3.org/TR/html4/strict.dtd")
+        );
+        assert!(!doctype.force_quirks);
+
+        let doctype = Doctype::parse(" html SYSTEM \"about:legacy-compat\"");
+        assert_eq!(doctype.public_id, None);
+        assert_eq!(doctype.system_id.as_deref(), Some("about:legacy-compat"));
+    }
+
+    #[test]
+    fn it_forces_quirks_for_malformed_doctypes() {
+        assert!(Doctype::parse("").force_quirks);
+        assert!(Doctype::parse(" html PUBLIC").force_quirks);
+        assert!(Doctype::parse(" html PUBLIC \"unclosed").force_quirks);
+        assert!(Doctype::parse(" html bogus").force_quirks);
+    }
+
+    #[test]
+    fn it_selects_the_quirks_mode() {
+        let mode = |content: &str| Doctype::parse(content).quirks_mode();
+        assert_eq!(mode(" html"), QuirksMode::NoQuirks);
+        assert_eq!(
+            mode(" html SYSTEM \"about:legacy-compat\""),
+            QuirksMode::NoQuirks
+        );
+        assert_eq!(
+            mode(" html PUBLIC \"-//W3C//DTD HTML 4.01//EN\""),
+            QuirksMode::NoQuirks
+        );
+        assert_eq!(mode(" svg"), QuirksMode::Quirks);
+        assert_eq!(
+            mode(" html PUBLIC \"-//W3C//DTD HTML 3.2 Final//EN\""),
+            QuirksMode::Quirks
+        );
+        assert_eq!(
+            mode(" html PUBLIC \"-//W3C//DTD HTML 4.01 Transitional//EN\""),
+            QuirksMode::Quirks
+        );
+        assert_eq!(
+            mode(" html PUBLIC \"-//W3C//DTD HTML 4.01 Transitional//EN\" \"http://www.w3.org/TR/html4/loose.dtd\""),
+            QuirksMode::LimitedQuirks
+        );
+        assert_eq!(
+            mode(" html PUBLIC \"-//w3c//dtd xhtml 1.0 transitional//en\""),
+            QuirksMode::LimitedQuirks
+        );
+    }
+}
@@ -1,12 +1,16 @@
+use super::attribute::Attributes;
+use super::html;
+use super::json;
 use super::node::Node;
+use super::options::JsonOptions;
 use super::span::SourceSpan;
-use serde::{Serialize, Serializer};
-use std::collections::{BTreeMap, HashMap};
+use crate::selector::Selector;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::default::Default;
-use std::result::Result;
 
 /// Normal: `<div></div>` or Void: `<meta/>`and `<meta>`
-#[derive(Debug, Clone, Serialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 // TODO: Align with: https://html.spec.whatwg.org/multipage/syntax.html#elements-2
 pub enum ElementVariant {
@@ -16,55 +20,139 @@ pub enum ElementVariant {
     Void,
 }
 
-pub type Attributes = HashMap<String, Option<String>>;
-
 /// Most of the parsed html nodes are elements, except for text
-#[derive(Debug, Clone, Serialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
-pub struct Element {
+pub struct Element<'a> {
     /// The id of the element
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<Cow<'a, str>>,
 
     /// The name / tag of the element
-    pub name: String,
+    pub name: Cow<'a, str>,
 
     /// The element variant, if it is of type void or not
     pub variant: ElementVariant,
 
-    /// All of the elements attributes, except id and class
-    #[serde(skip_serializing_if = "HashMap::is_empty")]
-    #[serde(serialize_with = "ordered_map")]
-    pub attributes: Attributes,
+    /// All of the elements attributes in source order, except id and class. Repeated id and
+    /// class attributes are kept here, as only the first ones are used for `id` and `classes`.
+    #[serde(default, skip_serializing_if = "Attributes::is_empty")]
+    pub attributes: Attributes<'a>,
 
     /// All of the elements classes
-    #[serde(skip_serializing_if = "Vec::is_empty")]
-    pub classes: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub classes: Vec<Cow<'a, str>>,
+
+    /// Where the id and class were among the attributes of the start tag, see
+    /// [`Element::id_position`] and [`Element::class_position`]
+    #[serde(skip)]
+    pub split_positions: SplitPositions,
 
     /// All of the elements child nodes
-    #[serde(skip_serializing_if = "Vec::is_empty")]
-    pub children: Vec<Node>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<Node<'a>>,
 
     /// Span of the element in the parsed source
+    #[serde(skip_serializing, default)]
+    pub source_span: SourceSpan,
+
+    /// Span of the start tag, ex: `<div id="a">`
+    #[serde(skip)]
+    pub start_tag_span: SourceSpan,
+
+    /// Span of the end tag, ex: `</div>`. Void elements, and elements whose end tag is implied,
+    /// ex: the first `li` in `<li>a<li>b`, have no end tag
     #[serde(skip)]
-    pub source_span: SourceSpan
+    pub end_tag_span: Option<SourceSpan>,
+
+    /// Span of everything between the start tag and the end tag. Void elements have no content
+    #[serde(skip)]
+    pub content_span: Option<SourceSpan>,
 }
 
-impl Default for Element {
+impl Default for Element<'_> {
     fn default() -> Self {
         Self {
             id: None,
-            name: "".to_string(),
+            name: Cow::Borrowed(""),
             variant: ElementVariant::Void,
             classes: vec![],
-            attributes: HashMap::new(),
+            split_positions: SplitPositions::default(),
+            attributes: Attributes::new(),
             children: vec![],
-            source_span: SourceSpan::default()
+            source_span: SourceSpan::default(),
+            start_tag_span: SourceSpan::default(),
+            end_tag_span: None,
+            content_span: None,
         }
     }
 }
 
-fn ordered_map<S: Serializer>(value: &Attributes, serializer: S) -> Result<S::Ok, S::Error> {
-    let ordered: BTreeMap<_, _> = value.iter().collect();
-    ordered.serialize(serializer)
+/// The positions of the id and class among all attributes of a start tag. They can only be read,
+/// through [`Element::id_position`] and [`Element::class_position`], so that they match the
+/// attributes of the element.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SplitPositions {
+    pub(crate) id: Option<usize>,
+    pub(crate) class: Option<usize>,
+}
+
+impl<'a> Element<'a> {
+    /// The position of the id among all attributes of the start tag, so that it's serialized in
+    /// source order. `None` puts it before the other attributes
+    pub fn id_position(&self) -> Option<usize> {
+        self.split_positions.id
+    }
+
+    /// The position of the class among all attributes of the start tag, see
+    /// [`Element::id_position`]
+    pub fn class_position(&self) -> Option<usize> {
+        self.split_positions.class
+    }
+
+    /// Returns all descendants of the element that match the css selector, in document order.
+    /// See [`Selector::select_in`].
+    pub fn select(&self, selector: &str) -> crate::Result<Vec<&Element<'_>>> {
+        Ok(Selector::parse(selector)?.select_in(self))
+    }
+
+    /// Detaches the element, and all of its children, from the parsed input, see
+    /// [`Dom::into_owned`](crate::Dom::into_owned)
+    pub fn into_owned(self) -> Element<'static> {
+        Element {
+            id: self.id.map(|id| Cow::Owned(id.into_owned())),
+            name: Cow::Owned(self.name.into_owned()),
+            variant: self.variant,
+            attributes: self.attributes.into_owned(),
+            classes: self
+                .classes
+                .into_iter()
+                .map(|class| Cow::Owned(class.into_owned()))
+                .collect(),
+            split_positions: self.split_positions,
+            children: self.children.into_iter().map(Node::into_owned).collect(),
+            source_span: self.source_span,
+            start_tag_span: self.start_tag_span,
+            end_tag_span: self.end_tag_span,
+            content_span: self.content_span,
+        }
+    }
+
+    /// Serializes the element, including its own start and end tag, back to html.
+    pub fn outer_html(&self) -> String {
+        self.to_string()
+    }
+
+    /// Serializes the children of the element back to html.
+    pub fn inner_html(&self) -> String {
+        let mut html = String::new();
+        // Writing to a String can't fail
+        let _ = html::write_children(self, &mut html);
+        html
+    }
+
+    /// Serializes the element, and all of its children, to json, see [`JsonOptions`].
+    pub fn to_json_with(&self, options: &JsonOptions) -> crate::Result<String> {
+        json::to_string(&json::Json::new(self, options), options)
+    }
 }
@@ -0,0 +1,203 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// Named character references, keyed by the name without the leading `&`. Legacy references
+/// that may appear without a trailing semicolon are listed both with and without it.
+fn named_references() -> &'static HashMap<&'static str, &'static str> {
+    static REFERENCES: OnceLock<HashMap<&'static str, &'static str>> = OnceLock::new();
+    REFERENCES.get_or_init(|| {
+        entities::ENTITIES
+            .iter()
+            .map(|entity| (&entity.entity[1..], entity.characters))
+            .collect()
+    })
+}
+
+/// The longest named character reference, `&CounterClockwiseContourIntegral;`, without the `&`
+const MAX_NAME_LENGTH: usize = 32;
+
+/// Replacements for numeric references to the C1 control range.
+/// See: https://html.spec.whatwg.org/multipage/parsing.html#numeric-character-reference-end-state
+const C1_REPLACEMENTS: [(u32, char); 27] = [
+    (0x80, '\u{20AC}'),
+    (0x82, '\u{201A}'),
+    (0x83, '\u{0192}'),
+    (0x84, '\u{201E}'),
+    (0x85, '\u{2026}'),
+    (0x86, '\u{2020}'),
+    (0x87, '\u{2021}'),
+    (0x88, '\u{02C6}'),
+    (0x89, '\u{2030}'),
+    (0x8A, '\u{0160}'),
+    (0x8B, '\u{2039}'),
+    (0x8C, '\u{0152}'),
+    (0x8E, '\u{017D}'),
+    (0x91, '\u{2018}'),
+    (0x92, '\u{2019}'),
+    (0x93, '\u{201C}'),
+    (0x94, '\u{201D}'),
+    (0x95, '\u{2022}'),
+    (0x96, '\u{2013}'),
+    (0x97, '\u{2014}'),
+    (0x98, '\u{02DC}'),
+    (0x99, '\u{2122}'),
+    (0x9A, '\u{0161}'),
+    (0x9B, '\u{203A}'),
+    (0x9C, '\u{0153}'),
+    (0x9E, '\u{017E}'),
+    (0x9F, '\u{0178}'),
+];
+
+/// Decodes named and numeric character references, ex: `&amp;`, `&#60;` and `&#x27;`, following
+/// https://html.spec.whatwg.org/multipage/parsing.html#character-reference-state
+///
+/// Inside attribute values, legacy references without a semicolon that are followed by `=` or an
+/// alphanumeric character are left as is, so that urls like `?a=1&copy=2` are kept intact.
+pub(crate) fn decode(input: &str, in_attribute: bool) -> Cow<'_, str> {
+    if !input.contains('&') {
+        return Cow::Borrowed(input);
+    }
+
+    let mut output = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(index) = rest.find('&') {
+        output.push_str(&rest[..index]);
+        rest = &rest[index + 1..];
+
+        let consumed = match rest.strip_prefix('#') {
+            Some(numeric) => decode_numeric(numeric, &mut output).map(|length| length + 1),
+            None => decode_named(rest, in_attribute, &mut output),
+        };
+        match consumed {
+            Some(length) => rest = &rest[length..],
+            None => output.push('&'),
+        }
+    }
+    output.push_str(rest);
+    Cow::Owned(output)
+}
+
+/// Decodes the reference at the start of `input` (after `&`), returning the consumed length.
+fn decode_named(input: &str, in_attribute: bool, output: &mut String) -> Option<usize> {
+    let name_length = input
+        .bytes()
+        .take(MAX_NAME_LENGTH)
+        .take_while(u8::is_ascii_alphanumeric)
+        .count();
+    if name_length == 0 {
+        return None;
+    }
+
+    let references = named_references();
+    if input[name_length..].starts_with(';') {
+        if let Some(characters) = references.get(&input[..=name_length]) {
+            output.push_str(characters);
+            return Some(name_length + 1);
+        }
+    }
+
+    // Only the legacy references can be used without a semicolon, and the longest match wins
+    for length in (1..=name_length).rev() {
+        if let Some(characters) = references.get(&input[..length]) {
+            let next = input[length..].chars().next();
+            if in_attribute && matches!(next, Some(c) if c == '=' || c.is_ascii_alphanumeric()) {
+                return None;
+            }
+            output.push_str(characters);
+            return Some(length);
+        }
+    }
+    None
+}
+
+/// Decodes the reference at the start of `input` (after `&#`), returning the consumed length.
+fn decode_numeric(input: &str, output: &mut String) -> Option<usize> {
+    let (radix, prefix_length) = match input.as_bytes().first() {
+        Some(b'x') | Some(b'X') => (16, 1),
+        _ => (10, 0),
+    };
+    let digits = &input[prefix_length..];
+    let digits_length = digits
+        .bytes()
+        .take_while(|b| match radix {
+            16 => b.is_ascii_hexdigit(),
+            _ => b.is_ascii_digit(),
+        })
+        .count();
+    if digits_length == 0 {
+        return None;
+    }
+
+    // Saturate on overflow, as the value is out of range either way
+    let code = u32::from_str_radix(&digits[..digits_length], radix).unwrap_or(u32::MAX);
+    let character = match code {
+        0 => '\u{FFFD}',
+        0x80..=0x9F => C1_REPLACEMENTS
+            .iter()
+            .find(|(from, _)| *from == code)
+            .map(|(_, to)| *to)
+            .unwrap_or_else(|| char::from_u32(code).expect("c1 control")),
+        // Surrogates and values outside of the unicode range
+        code => char::from_u32(code).unwrap_or('\u{FFFD}'),
+    };
+    output.push(character);
+
+    let mut length = prefix_length + digits_length;
+    if digits[digits_length..].starts_with(';') {
+        length += 1;
+    }
+    Some(length)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_decodes_named_references() {
+        assert_eq!(
+            decode("a &amp; b &lt;&gt; &nbsp;", false),
+            "a & b <> \u{a0}"
+        );
+        assert_eq!(
+            decode("&NotNestedGreaterGreater;", false),
+            "\u{2AA2}\u{338}"
+        );
+        assert_eq!(decode("&unknown; &", false), "&unknown; &");
+    }
+
+    #[test]
+    fn it_decodes_legacy_named_references_without_semicolon() {
+        assert_eq!(decode("&copy 2020", false), "\u{a9} 2020");
+        assert_eq!(decode("&notit;", false), "\u{ac}it;");
+        assert_eq!(decode("&ampx", false), "&x");
+        assert_eq!(decode("&hellip", false), "&hellip");
+    }
+
+    #[test]
+    fn it_keeps_legacy_references_followed_by_alphanumerics_in_attributes() {
+        assert_eq!(decode("?a=1&copy=2&ampx", true), "?a=1&copy=2&ampx");
+        assert_eq!(decode("?a=1&copy;=2&amp", true), "?a=1\u{a9}=2&");
+    }
+
+    #[test]
+    fn it_decodes_numeric_references() {
+        assert_eq!(decode("&#60;&#x3E;&#X27;&#39", false), "<>''");
+        assert_eq!(
+            decode("&#0;&#xD800;&#x110000;", false),
+            "\u{FFFD}\u{FFFD}\u{FFFD}"
+        );
+        assert_eq!(
+            decode("&#x80;&#150;&#x81;", false),
+            "\u{20AC}\u{2013}\u{81}"
+        );
+        assert_eq!(decode("&#99999999999999;", false), "\u{FFFD}");
+        assert_eq!(decode("&#; &#x;", false), "&#; &#x;");
+    }
+
+    #[test]
+    fn it_borrows_input_without_references() {
+        assert!(matches!(decode("no references", false), Cow::Borrowed(_)));
+    }
+}
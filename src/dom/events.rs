@@ -0,0 +1,373 @@
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::time::Instant;
+
+use super::attribute::Attributes;
+use super::doctype::Doctype;
+use super::html;
+use super::node::{Cdata, Comment, ProcessingInstruction, Text};
+use super::options::{ParseOptions, Tokenizer};
+use super::span::SourceSpan;
+use super::{Context, Dom, Mode};
+use crate::error::{Error, Limit, ParseError};
+use crate::tokenizer::{Lexer, Span, Token};
+use crate::Result;
+
+/// A part of the input, as read by [`Events`].
+///
+/// Elements are a start tag and an end tag event, ex: `<p>text</p>` is a start tag, a text and
+/// an end tag event. The events are the tags as they are written, so end tags that are left out,
+/// like the one of `p` in `<p>one<p>two`, aren't added, and nothing is moved or reopened as in
+/// the tree.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum Event<'a> {
+    /// Ex: `<!doctype html>`
+    Doctype(Doctype),
+    /// Ex: `<a href="/">` and `<br/>`, with all attributes in source order, including `id`,
+    /// `class` and repeated keys
+    StartTag {
+        name: Cow<'a, str>,
+        attributes: Attributes<'a>,
+        self_closing: bool,
+        span: SourceSpan,
+    },
+    /// Ex: `</a>`
+    EndTag {
+        name: Cow<'a, str>,
+        span: SourceSpan,
+    },
+    /// Text, including text that only contains whitespace, and the content of raw text elements,
+    /// like `script`
+    Text(Text<'a>),
+    /// Ex: `<!-- comment -->`, where the whitespace around the comment is part of it instead of a
+    /// text event
+    Comment(Comment<'a>),
+    /// Ex: `<![CDATA[ x < y ]]>`, which the tree keeps as a comment in html outside of svg and
+    /// math
+    Cdata(Cdata<'a>),
+    ProcessingInstruction(ProcessingInstruction<'a>),
+    /// Ex: `<!--[if IE]>`, and `<!--[if !IE]><!-->` where the content is revealed
+    ConditionalCommentStart {
+        condition: Cow<'a, str>,
+        revealed: bool,
+        span: SourceSpan,
+    },
+    /// Ex: `<![endif]-->`
+    ConditionalCommentEnd {
+        span: SourceSpan,
+    },
+}
+
+/// Reads the input as a flat list of [`Event`]s, without building a tree, ex: to find every link
+/// in a large page. The events are read with the same tokenizer as [`Dom::parse`], which builds
+/// its tree from them, and the first error ends them.
+///
+/// Names, text and attributes follow the [`ParseOptions`], except the ones that are about the
+/// tree: comments and whitespace text are always events, and `id` and `class` are kept with the
+/// other attributes. The limits on the input size, the attributes and the time apply too.
+///
+/// ```rust
+/// use html_parser::{Event, Events, Result};
+///
+/// fn main() -> Result<()> {
+///     let html = r#"<p><a href="/one">one</a> and <a href="/two">two</a></p>"#;
+///     let mut links = vec![];
+///     for event in Events::new(html) {
+///         if let Event::StartTag { name, attributes, .. } = event? {
+///             if name == "a" {
+///                 links.extend(attributes.get("href").cloned().flatten());
+///             }
+///         }
+///     }
+///     assert_eq!(links, ["/one", "/two"]);
+///     Ok(())
+/// }
+/// ```
+pub struct Events<'a> {
+    context: Context<'a>,
+    reader: Reader<'a, Tokens<'a>>,
+    /// The text and end tag of a raw text element, after its start tag was returned
+    pending: VecDeque<Event<'a>>,
+    /// An error from before the first token, which is returned instead of any event
+    error: Option<Error>,
+}
+
+type Tokens<'a> = Box<dyn Iterator<Item = std::result::Result<Token<'a>, ParseError>> + 'a>;
+
+impl<'a> Events<'a> {
+    /// Reads the input with the default options
+    pub fn new(input: &'a str) -> Self {
+        Self::with_options(input, &ParseOptions::default())
+    }
+
+    /// Reads the input with the given options, see [`ParseOptions`]
+    pub fn with_options(input: &'a str, options: &ParseOptions) -> Self {
+        let mut error = None;
+        let input = match options.max_input_size {
+            Some(max) if input.len() > max => {
+                error = Some(Error::LimitExceeded(Limit::InputSize(max)));
+                ""
+            }
+            _ => input,
+        };
+        let tokens: Tokens<'a> = match options.tokenizer {
+            Tokenizer::Streaming => Box::new(Lexer::new(input, false)),
+            #[cfg(feature = "pest")]
+            Tokenizer::Pest => match crate::grammar::tokens(input, false) {
+                Ok(tokens) => Box::new(tokens),
+                Err(grammar_error) => {
+                    error = error.or(Some(grammar_error.into()));
+                    Box::new(std::iter::empty())
+                }
+            },
+        };
+        Self {
+            context: Context::new(input, options, Mode::Default),
+            reader: Reader::new(tokens),
+            pending: VecDeque::new(),
+            error,
+        }
+    }
+}
+
+impl<'a> Iterator for Events<'a> {
+    type Item = Result<Event<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(error) = self.error.take() {
+            self.reader.done = true;
+            return Some(Err(error));
+        }
+        if let Some(event) = self.pending.pop_front() {
+            return Some(Ok(event));
+        }
+        // Only the tree builder reports warnings, ex: duplicate attributes in strict mode
+        let event = match self.reader.next(&self.context, &mut vec![])? {
+            Ok(RawEvent::Event { event, .. }) => event,
+            Ok(RawEvent::RawText {
+                start_tag,
+                text,
+                end_tag,
+                ..
+            }) => {
+                if !text.value.is_empty() {
+                    self.pending.push_back(Event::Text(text));
+                }
+                self.pending.extend(end_tag.map(|(event, _)| event));
+                start_tag
+            }
+            Ok(RawEvent::BogusComment { comment, .. }) => Event::Comment(comment),
+            Err(ReadError::Parsing(error)) => return Some(Err(error.into())),
+            Err(ReadError::Limit(limit, _)) => return Some(Err(Error::LimitExceeded(limit))),
+        };
+        Some(Ok(event))
+    }
+}
+
+/// An event with the spans of its tokens, which the tree builder needs, as they include the
+/// whitespace around the tokens
+// Raw text elements are rare, and events are built into the tree one at a time
+#[allow(clippy::large_enum_variant)]
+pub(super) enum RawEvent<'a> {
+    Event {
+        event: Event<'a>,
+        span: Span<'a>,
+    },
+    /// A raw text element, ex: `<script>`, whose start tag, text and end tag are added to the
+    /// tree together. The text may be empty, and without an end tag it goes to the end of the
+    /// input.
+    RawText {
+        start_tag: Event<'a>,
+        start: Span<'a>,
+        text: Text<'a>,
+        content: Span<'a>,
+        end_tag: Option<(Event<'a>, Span<'a>)>,
+    },
+    /// Markup that can't be parsed, which is only an event in lenient mode
+    BogusComment {
+        comment: Comment<'a>,
+        span: Span<'a>,
+        unclosed: bool,
+    },
+}
+
+impl<'a> RawEvent<'a> {
+    /// The span of the event, which is the one of the start tag for raw text elements
+    pub fn span(&self) -> Span<'a> {
+        match self {
+            RawEvent::Event { span, .. } | RawEvent::BogusComment { span, .. } => *span,
+            RawEvent::RawText { start, .. } => *start,
+        }
+    }
+}
+
+/// Why the reading stopped
+pub(super) enum ReadError<'a> {
+    Parsing(ParseError),
+    /// A limit of the options, with where it was exceeded
+    Limit(Limit, Span<'a>),
+}
+
+/// Builds the events of the tokens, and checks the limits of the options that apply to tokens.
+/// The first error ends the events.
+pub(super) struct Reader<'a, I> {
+    tokens: I,
+    /// The number of tokens that were read
+    index: usize,
+    done: bool,
+    input: std::marker::PhantomData<&'a str>,
+}
+
+impl<'a, I> Reader<'a, I>
+where
+    I: Iterator<Item = std::result::Result<Token<'a>, ParseError>>,
+{
+    pub fn new(tokens: I) -> Self {
+        Self {
+            tokens,
+            index: 0,
+            done: false,
+            input: std::marker::PhantomData,
+        }
+    }
+
+    /// The next event, where warnings about the tokens are added to `errors`
+    pub fn next(
+        &mut self,
+        context: &Context<'a>,
+        errors: &mut Vec<ParseError>,
+    ) -> Option<std::result::Result<RawEvent<'a>, ReadError<'a>>> {
+        if self.done {
+            return None;
+        }
+        let result = self.read(context, errors)?;
+        self.done = result.is_err();
+        Some(result)
+    }
+
+    fn read(
+        &mut self,
+        context: &Context<'a>,
+        errors: &mut Vec<ParseError>,
+    ) -> Option<std::result::Result<RawEvent<'a>, ReadError<'a>>> {
+        let token = match self.tokens.next()? {
+            Ok(token) => token,
+            Err(error) => return Some(Err(ReadError::Parsing(error))),
+        };
+        if let Some((limit, span)) = exceeded_limit(&token, self.index, context) {
+            return Some(Err(ReadError::Limit(limit, span)));
+        }
+        self.index += 1;
+        Some(Ok(raw_event(token, errors, context)))
+    }
+}
+
+/// The limit of the options that the token exceeds, with where it's exceeded. The number of
+/// nodes is checked by the tree builder.
+fn exceeded_limit<'a>(
+    token: &Token<'a>,
+    index: usize,
+    context: &Context,
+) -> Option<(Limit, Span<'a>)> {
+    let options = &context.options;
+    // Reading the clock for every token would slow the parsing down
+    if let Some(deadline) = context.deadline.filter(|_| index % 64 == 0) {
+        if Instant::now() >= deadline {
+            let timeout = options.timeout.unwrap_or_default();
+            return Some((Limit::Timeout(timeout), token.span().trim()));
+        }
+    }
+    let tag = match token {
+        Token::StartTag(tag) | Token::RawText { start_tag: tag, .. } => tag,
+        _ => return None,
+    };
+    if let Some(max) = options.max_attributes {
+        if let Some(attribute) = tag.attributes.get(max) {
+            return Some((Limit::Attributes(max), attribute.span.trim()));
+        }
+    }
+    let max = options.max_attribute_length?;
+    tag.attributes
+        .iter()
+        .map(|attribute| attribute.span.trim())
+        .find(|span| span.end() - span.start() > max)
+        .map(|span| (Limit::AttributeLength(max), span))
+}
+
+fn raw_event<'a>(
+    token: Token<'a>,
+    errors: &mut Vec<ParseError>,
+    context: &Context,
+) -> RawEvent<'a> {
+    let span = token.span();
+    let event = match token {
+        Token::Doctype { span, content } => {
+            Event::Doctype(Dom::build_doctype(span, content, context))
+        }
+        Token::Text(span) => Event::Text(Dom::build_text(span, true, context)),
+        Token::Comment { span, value } => Event::Comment(Dom::build_comment(span, value, context)),
+        Token::BogusComment {
+            span,
+            value,
+            unclosed,
+        } => {
+            return RawEvent::BogusComment {
+                comment: Dom::build_comment(span, value, context),
+                span,
+                unclosed,
+            }
+        }
+        Token::Cdata { span, content } => Event::Cdata(Cdata {
+            value: Cow::Borrowed(content),
+            source_span: Dom::build_source_span(span, context),
+        }),
+        Token::ProcessingInstruction { span, target, data } => Event::ProcessingInstruction(
+            Dom::build_processing_instruction(span, target, data, context),
+        ),
+        Token::StartTag(tag) => Dom::build_start_tag(tag, errors, context),
+        Token::EndTag { span, name } => Event::EndTag {
+            name: Dom::build_name(name, context),
+            span: Dom::build_source_span(span.trim(), context),
+        },
+        Token::RawText {
+            start_tag,
+            content,
+            end_tag,
+        } => {
+            // The content of title and textarea may contain character references, but the
+            // content of script and style is always kept as is
+            let decode = !html::is_raw_text_element(start_tag.name);
+            let start = start_tag.span.trim();
+            let end_tag = end_tag.map(Span::trim).map(|span| {
+                // Ex: `</ script >`
+                let tag = span.as_str();
+                let event = Event::EndTag {
+                    name: Dom::build_name(tag[2..tag.len() - 1].trim(), context),
+                    span: Dom::build_source_span(span, context),
+                };
+                (event, span)
+            });
+            return RawEvent::RawText {
+                start_tag: Dom::build_start_tag(start_tag, errors, context),
+                start,
+                text: Dom::build_text(content, decode, context),
+                content,
+                end_tag,
+            };
+        }
+        Token::ConditionalCommentStart {
+            span,
+            condition,
+            revealed,
+        } => Event::ConditionalCommentStart {
+            condition: Cow::Borrowed(condition.trim()),
+            revealed,
+            span: Dom::build_source_span(span.trim(), context),
+        },
+        Token::ConditionalCommentEnd(span) => Event::ConditionalCommentEnd {
+            span: Dom::build_source_span(span.trim(), context),
+        },
+    };
+    RawEvent::Event { event, span }
+}
@@ -1,29 +1,80 @@
-use crate::error::Error;
-use crate::Result;
-use crate::Rule;
-use pest::error::Error as PestError;
+use crate::dom::span::LineIndex;
+#[cfg(feature = "pest")]
+use crate::dom::span::SourceSpan;
+use crate::error::{ParseError, ParseErrorKind};
+#[cfg(feature = "pest")]
+use crate::grammar::Rule;
+#[cfg(feature = "pest")]
+use pest::error::{Error as PestError, ErrorVariant, InputLocation, LineColLocation};
 
-/// This function abstracts the formatting of errors away from the core logic inside parser,
-/// so that the file is easier to read.
-pub fn error_msg(error: PestError<Rule>) -> Result<super::Dom> {
-    let message = error.renamed_rules(|rule| match *rule {
+const ELEMENT_NAME: &str = "element name";
+const RAW_TEXT_ELEMENT_NAME: &str = "raw text element name (style or script)";
+
+/// A readable name for a rule of the grammar
+#[cfg(feature = "pest")]
+fn rule_name(rule: &Rule) -> String {
+    match *rule {
         Rule::EOI => "end of input".to_string(),
         Rule::doctype => "doctype element".to_string(),
         Rule::node_text => "text node".to_string(),
-        Rule::node_element => "element node".to_string(),
-        Rule::el_void => "void element".to_string(),
-        Rule::el_void_xml => "void element with xml ending (/>)".to_string(),
-        Rule::el_process_instruct => "xml processing instruction".to_string(),
+        Rule::node_process_instruct => "xml processing instruction".to_string(),
         Rule::el_raw_text => "element with raw text (style or script)".to_string(),
-        Rule::el_normal => "normal element".to_string(),
-        Rule::el_dangling => "".to_string(),
+        Rule::el_start => "start tag".to_string(),
+        Rule::el_end => "end tag".to_string(),
         Rule::attr => "attribute (key=\"value\")".to_string(),
         Rule::attr_key => "attribute key".to_string(),
         Rule::attr_value => "attribute value".to_string(),
-        Rule::el_name => "element name".to_string(),
-        Rule::el_void_name_html => "void element name".to_string(),
+        Rule::el_name => ELEMENT_NAME.to_string(),
+        Rule::el_raw_text_name => RAW_TEXT_ELEMENT_NAME.to_string(),
         // TODO: Continue with this
-        x => format!("{:?} ", x),
-    });
-    Err(Error::Parsing(message.to_string()))
+        x => format!("{:?}", x),
+    }
+}
+
+/// Converts an error of the grammar into a [`ParseError`], with readable names for the rules.
+/// This keeps the formatting of errors away from the core logic inside parser.
+#[cfg(feature = "pest")]
+pub(crate) fn syntax_error(error: PestError<Rule>) -> ParseError {
+    let expected = match &error.variant {
+        ErrorVariant::ParsingError { positives, .. } => positives
+            .iter()
+            .map(rule_name)
+            .filter(|name| !name.is_empty())
+            .collect(),
+        ErrorVariant::CustomError { .. } => vec![],
+    };
+    let error = error.renamed_rules(rule_name);
+    let (start, end) = match error.location {
+        InputLocation::Pos(pos) => (pos, pos),
+        InputLocation::Span(span) => span,
+    };
+    let ((start_line, start_column), (end_line, end_column)) = match error.line_col {
+        LineColLocation::Pos(pos) => (pos, pos),
+        LineColLocation::Span(start, end) => (start, end),
+    };
+
+    ParseError {
+        expected,
+        snippet: Some(error.to_string().into()),
+        ..ParseError::new(
+            ParseErrorKind::Syntax,
+            error.variant.message(),
+            SourceSpan::new(start, end, start_line, end_line, start_column, end_column),
+        )
+    }
+}
+
+/// The syntax error of markup that starts with `<!` but isn't a doctype, comment or CDATA
+/// section, at the position after the `<`. It's the only syntax error of the grammar, which
+/// fails there because the `<` isn't followed by an element name either.
+pub(crate) fn unexpected_markup(input: &str, position: usize) -> ParseError {
+    ParseError {
+        expected: vec![ELEMENT_NAME.to_string(), RAW_TEXT_ELEMENT_NAME.to_string()],
+        ..ParseError::new(
+            ParseErrorKind::Syntax,
+            format!("expected {} or {}", ELEMENT_NAME, RAW_TEXT_ELEMENT_NAME),
+            LineIndex::new(input).span(position, position),
+        )
+    }
+    .with_snippet(input)
 }
@@ -0,0 +1,204 @@
+use super::element::{Element, ElementVariant};
+use super::node::Node;
+use super::Dom;
+use std::fmt::{self, Write};
+
+/// Elements that never have an end tag in html.
+/// See: https://html.spec.whatwg.org/multipage/syntax.html#void-elements
+const VOID_ELEMENTS: [&str; 16] = [
+    "area", "base", "br", "col", "command", "embed", "hr", "img", "input", "keygen", "link",
+    "meta", "param", "source", "track", "wbr",
+];
+
+/// Elements whose text content is written as-is, without escaping.
+/// See: https://html.spec.whatwg.org/multipage/parsing.html#serialising-html-fragments
+const RAW_TEXT_ELEMENTS: [&str; 7] = [
+    "script",
+    "style",
+    "xmp",
+    "iframe",
+    "noembed",
+    "noframes",
+    "plaintext",
+];
+
+/// Elements in svg and math whose content is html again.
+/// See: https://html.spec.whatwg.org/multipage/parsing.html#html-integration-point
+const HTML_INTEGRATION_POINTS: [&str; 9] = [
+    "foreignObject",
+    "desc",
+    "title",
+    "mi",
+    "mo",
+    "mn",
+    "ms",
+    "mtext",
+    "annotation-xml",
+];
+
+/// Whether the content of an element is foreign content (svg or math) instead of html, where
+/// `in_foreign_content` is set if the element itself is in foreign content.
+pub(crate) fn has_foreign_content(name: &str, in_foreign_content: bool) -> bool {
+    if name.eq_ignore_ascii_case("svg") || name.eq_ignore_ascii_case("math") {
+        return true;
+    }
+    in_foreign_content
+        && !HTML_INTEGRATION_POINTS
+            .iter()
+            .any(|x| x.eq_ignore_ascii_case(name))
+}
+
+pub(crate) fn is_void_element(name: &str) -> bool {
+    VOID_ELEMENTS.iter().any(|x| x.eq_ignore_ascii_case(name))
+}
+
+pub(crate) fn is_raw_text_element(name: &str) -> bool {
+    RAW_TEXT_ELEMENTS
+        .iter()
+        .any(|x| x.eq_ignore_ascii_case(name))
+}
+
+pub(crate) fn write_dom<W: Write>(dom: &Dom, out: &mut W) -> fmt::Result {
+    for node in &dom.children {
+        write_node(node, false, out)?;
+    }
+    Ok(())
+}
+
+/// Writes a node as html. `raw` is set when the node is a child of a raw text element,
+/// in which case text is written without escaping.
+pub(crate) fn write_node<W: Write>(node: &Node, raw: bool, out: &mut W) -> fmt::Result {
+    match node {
+        Node::Text(text) if raw => out.write_str(text),
+        Node::Text(text) => write_escaped(text, false, out),
+        Node::Comment(comment) => write!(out, "<!--{}-->", comment),
+        Node::Doctype(doctype) => write!(out, "{}", doctype),
+        Node::Cdata(cdata) => write!(out, "<![CDATA[{}]]>", cdata),
+        Node::ConditionalComment(comment) => {
+            write!(out, "<!--[if {}]>", comment.condition)?;
+            if comment.downlevel_revealed {
+                out.write_str("<!-->")?;
+            }
+            for child in &comment.children {
+                write_node(child, raw, out)?;
+            }
+            if comment.downlevel_revealed {
+                out.write_str("<!--")?;
+            }
+            out.write_str("<![endif]-->")
+        }
+        Node::ProcessingInstruction(instruction) if instruction.data.is_empty() => {
+            write!(out, "<?{}?>", instruction.target)
+        }
+        Node::ProcessingInstruction(instruction) => {
+            write!(out, "<?{} {}?>", instruction.target, instruction.data)
+        }
+        Node::Element(element) => write_element(element, out),
+    }
+}
+
+pub(crate) fn write_element<W: Write>(element: &Element, out: &mut W) -> fmt::Result {
+    write_start_tag(element, out)?;
+    // Void elements never have an end tag or children, even if they were built as `Normal`
+    if element.variant == ElementVariant::Void || is_void_element(&element.name) {
+        return Ok(());
+    }
+    write_children(element, out)?;
+    write!(out, "</{}>", element.name)
+}
+
+pub(crate) fn write_children<W: Write>(element: &Element, out: &mut W) -> fmt::Result {
+    let raw = is_raw_text_element(&element.name);
+    for child in &element.children {
+        write_node(child, raw, out)?;
+    }
+    Ok(())
+}
+
+fn write_start_tag<W: Write>(element: &Element, out: &mut W) -> fmt::Result {
+    write!(out, "<{}", element.name)?;
+
+    // The parser splits id and classes out of the attributes, so they are merged back here at
+    // their position in the start tag. Without a position they come first, and positions past the
+    // end, ex: after attributes were removed, put them last.
+    let classes = (!element.classes.is_empty()).then(|| element.classes.join(" "));
+    let mut split = [
+        (element.id_position(), "id", element.id.as_deref()),
+        (element.class_position(), "class", classes.as_deref()),
+    ];
+    split.sort_by_key(|(position, _, _)| *position);
+    let mut split = split.iter().peekable();
+
+    let mut attributes = element.attributes.iter();
+    let mut index = 0;
+    loop {
+        let next_split = split.next_if(|(position, _, _)| position.map_or(true, |x| x <= index));
+        if let Some((position, key, value)) = next_split {
+            // A removed id or class keeps its position, so that the others don't move
+            if let Some(value) = value {
+                write_attribute(key, Some(value), out)?;
+            }
+            index += usize::from(position.is_some());
+        } else if let Some(attribute) = attributes.next() {
+            write_attribute(&attribute.key, attribute.value.as_deref(), out)?;
+            index += 1;
+        } else {
+            break;
+        }
+    }
+    for (_, key, value) in split {
+        if let Some(value) = value {
+            write_attribute(key, Some(value), out)?;
+        }
+    }
+
+    match element.variant {
+        // Non-void elements without children can only be expressed with a self-closing tag
+        ElementVariant::Void if !is_void_element(&element.name) => out.write_str(" />"),
+        _ => out.write_char('>'),
+    }
+}
+
+fn write_attribute<W: Write>(key: &str, value: Option<&str>, out: &mut W) -> fmt::Result {
+    write!(out, " {}", key)?;
+    if let Some(value) = value {
+        out.write_str("=\"")?;
+        write_escaped(value, true, out)?;
+        out.write_char('"')?;
+    }
+    Ok(())
+}
+
+/// Escapes text according to the html serialization algorithm.
+/// See: https://html.spec.whatwg.org/multipage/parsing.html#escapingString
+fn write_escaped<W: Write>(text: &str, attribute_mode: bool, out: &mut W) -> fmt::Result {
+    for c in text.chars() {
+        match c {
+            '&' => out.write_str("&amp;")?,
+            '\u{a0}' => out.write_str("&nbsp;")?,
+            '"' if attribute_mode => out.write_str("&quot;")?,
+            '<' if !attribute_mode => out.write_str("&lt;")?,
+            '>' if !attribute_mode => out.write_str("&gt;")?,
+            c => out.write_char(c)?,
+        }
+    }
+    Ok(())
+}
+
+impl fmt::Display for Dom<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_dom(self, f)
+    }
+}
+
+impl fmt::Display for Node<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_node(self, false, f)
+    }
+}
+
+impl fmt::Display for Element<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_element(self, f)
+    }
+}
@@ -0,0 +1,207 @@
+use super::attribute::{Attribute, Attributes};
+use super::doctype::Doctype;
+use super::element::Element;
+use super::node::{ConditionalComment, Node, ProcessingInstruction};
+use super::options::{JsonOptions, JsonSchema};
+use super::Dom;
+use crate::Result;
+use serde::ser::{SerializeMap, SerializeSeq, Serializer};
+use serde::Serialize;
+
+pub(crate) fn to_string<T: Serialize>(value: &T, options: &JsonOptions) -> Result<String> {
+    Ok(if options.pretty {
+        serde_json::to_string_pretty(value)?
+    } else {
+        serde_json::to_string(value)?
+    })
+}
+
+/// Serializes a value with the schema and extra fields that are selected by the options
+pub(crate) struct Json<'a, T: ?Sized> {
+    value: &'a T,
+    options: &'a JsonOptions,
+}
+
+impl<'a, T: ?Sized> Json<'a, T> {
+    pub fn new(value: &'a T, options: &'a JsonOptions) -> Self {
+        Self { value, options }
+    }
+
+    fn with<U: ?Sized>(&self, value: &'a U) -> Json<'a, U> {
+        Json::new(value, self.options)
+    }
+
+    fn is_tagged(&self) -> bool {
+        self.options.schema == JsonSchema::Tagged
+    }
+}
+
+impl Serialize for Json<'_, Dom<'_>> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let dom = self.value;
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("treeType", &dom.tree_type)?;
+        if !dom.children.is_empty() {
+            map.serialize_entry("children", &self.with(dom.children.as_slice()))?;
+        }
+        if self.options.errors {
+            map.serialize_entry("errors", &dom.errors)?;
+        }
+        map.end()
+    }
+}
+
+impl Serialize for Json<'_, [Node<'_>]> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.value.len()))?;
+        for node in self.value {
+            seq.serialize_element(&self.with(node))?;
+        }
+        seq.end()
+    }
+}
+
+impl Serialize for Json<'_, Node<'_>> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let (kind, text, span) = match self.value {
+            Node::Element(element) => return self.with(element).serialize(serializer),
+            Node::Doctype(doctype) => return self.with(doctype).serialize(serializer),
+            Node::ProcessingInstruction(instruction) => {
+                return self.with(instruction).serialize(serializer)
+            }
+            Node::ConditionalComment(comment) => return self.with(comment).serialize(serializer),
+            Node::Text(text) => ("text", text.as_str(), &text.source_span),
+            Node::Comment(comment) => ("comment", comment.as_str(), &comment.source_span),
+            Node::Cdata(cdata) => ("cdata", cdata.as_str(), &cdata.source_span),
+        };
+        // CDATA sections are always tagged, as they aren't a part of the untagged schema
+        if !self.is_tagged() && kind != "cdata" {
+            return serializer.serialize_str(text);
+        }
+
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("type", kind)?;
+        map.serialize_entry("value", text)?;
+        if self.options.source_spans {
+            map.serialize_entry("sourceSpan", span)?;
+        }
+        map.end()
+    }
+}
+
+impl Serialize for Json<'_, Element<'_>> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let element = self.value;
+        let mut map = serializer.serialize_map(None)?;
+        if self.is_tagged() {
+            map.serialize_entry("type", "element")?;
+        }
+        if let Some(id) = &element.id {
+            map.serialize_entry("id", id)?;
+        }
+        map.serialize_entry("name", &element.name)?;
+        map.serialize_entry("variant", &element.variant)?;
+        if !element.attributes.is_empty() {
+            if self.is_tagged() {
+                map.serialize_entry("attributes", &self.with(&element.attributes))?;
+            } else {
+                map.serialize_entry("attributes", &element.attributes)?;
+            }
+        }
+        if !element.classes.is_empty() {
+            map.serialize_entry("classes", &element.classes)?;
+        }
+        if !element.children.is_empty() {
+            map.serialize_entry("children", &self.with(element.children.as_slice()))?;
+        }
+        if self.options.source_spans {
+            map.serialize_entry("sourceSpan", &element.source_span)?;
+        }
+        map.end()
+    }
+}
+
+/// A doctype is an object with a type in both schemas
+impl Serialize for Json<'_, Doctype> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let doctype = self.value;
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("type", "doctype")?;
+        if let Some(name) = &doctype.name {
+            map.serialize_entry("name", name)?;
+        }
+        if let Some(public_id) = &doctype.public_id {
+            map.serialize_entry("publicId", public_id)?;
+        }
+        if let Some(system_id) = &doctype.system_id {
+            map.serialize_entry("systemId", system_id)?;
+        }
+        if doctype.force_quirks {
+            map.serialize_entry("forceQuirks", &true)?;
+        }
+        if self.options.source_spans {
+            map.serialize_entry("sourceSpan", &doctype.source_span)?;
+        }
+        map.end()
+    }
+}
+
+/// A processing instruction is an object with a type in both schemas
+impl Serialize for Json<'_, ProcessingInstruction<'_>> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let instruction = self.value;
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("type", "processingInstruction")?;
+        map.serialize_entry("target", &instruction.target)?;
+        map.serialize_entry("data", &instruction.data)?;
+        if self.options.source_spans {
+            map.serialize_entry("sourceSpan", &instruction.source_span)?;
+        }
+        map.end()
+    }
+}
+
+/// A conditional comment is an object with a type in both schemas, so that it can be told apart
+/// from an element
+impl Serialize for Json<'_, ConditionalComment<'_>> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let comment = self.value;
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("type", "conditionalComment")?;
+        map.serialize_entry("condition", &comment.condition)?;
+        if comment.downlevel_revealed {
+            map.serialize_entry("downlevelRevealed", &true)?;
+        }
+        if !comment.children.is_empty() {
+            map.serialize_entry("children", &self.with(comment.children.as_slice()))?;
+        }
+        if self.options.source_spans {
+            map.serialize_entry("sourceSpan", &comment.source_span)?;
+        }
+        map.end()
+    }
+}
+
+/// The tagged schema keeps attributes as a list, so that duplicates aren't lost
+impl Serialize for Json<'_, Attributes<'_>> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.value.len()))?;
+        for attribute in self.value {
+            seq.serialize_element(&self.with(attribute))?;
+        }
+        seq.end()
+    }
+}
+
+impl Serialize for Json<'_, Attribute<'_>> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let attribute = self.value;
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("key", &attribute.key)?;
+        map.serialize_entry("value", &attribute.value)?;
+        if self.options.source_spans {
+            map.serialize_entry("sourceSpan", &attribute.source_span)?;
+        }
+        map.end()
+    }
+}
@@ -0,0 +1,323 @@
+use super::element::{Element, SplitPositions};
+use super::node::Node;
+use super::Dom;
+use std::borrow::Cow;
+
+// Methods to modify the tree. The same methods exist on Dom and Element, where the element
+// versions only touch the descendants of the element.
+//
+// The `*_where` methods edit every node that matches a predicate, while a single node is edited
+// through the path to it, see `node_at_mut`.
+//
+// Predicates are applied to nodes at any depth. With the exception of `remove_where`, which
+// checks a node before its children, the children of a node are processed before the node itself
+// and nodes that are inserted by the method are never visited.
+macro_rules! impl_manipulation {
+    ($type:ty) => {
+        impl<'a> $type {
+            /// Returns the node at the index path, where every index selects a child of the
+            /// previous node, ex: `[1, 0]` is the first child of the second child
+            pub fn node_at(&self, path: &[usize]) -> Option<&Node<'a>> {
+                let (last, parents) = path.split_last()?;
+                let mut children = self.children.as_slice();
+                for index in parents {
+                    children = children.get(*index)?.children()?;
+                }
+                children.get(*last)
+            }
+
+            /// Returns the node at the index path with mutable access, see `node_at`. This edits
+            /// a single node, unlike the `*_where` methods, which edit every node that matches a
+            /// predicate. Nodes are inserted and removed through the children of their parent,
+            /// ex: `node_at_mut(&[1])?.children_mut()?.remove(0)` removes the first child of the
+            /// second child
+            pub fn node_at_mut(&mut self, path: &[usize]) -> Option<&mut Node<'a>> {
+                let (last, parents) = path.split_last()?;
+                let mut children = &mut self.children;
+                for index in parents {
+                    children = children.get_mut(*index)?.children_mut()?;
+                }
+                children.get_mut(*last)
+            }
+
+            /// Removes all nodes, at any depth, that match the predicate, returning the number of
+            /// removed nodes
+            pub fn remove_where<F>(&mut self, mut predicate: F) -> usize
+            where
+                F: FnMut(&Node<'a>) -> bool,
+            {
+                remove_where(&mut self.children, &mut predicate)
+            }
+
+            /// Replaces all elements, at any depth, that match the predicate with their children,
+            /// returning the number of replaced elements
+            pub fn unwrap_where<F>(&mut self, mut predicate: F) -> usize
+            where
+                F: FnMut(&Element<'a>) -> bool,
+            {
+                let mut count = 0;
+                flat_map(&mut self.children, &mut |node| match node {
+                    Node::Element(element) if predicate(&element) => {
+                        count += 1;
+                        element.children
+                    }
+                    node => vec![node],
+                });
+                count
+            }
+
+            /// Wraps all nodes, at any depth, that match the predicate in a copy of `wrapper`,
+            /// which is appended after the existing children of the wrapper. Returns the number of
+            /// wrapped nodes
+            pub fn wrap_where<F>(&mut self, mut predicate: F, wrapper: &Element<'a>) -> usize
+            where
+                F: FnMut(&Node<'a>) -> bool,
+            {
+                let mut count = 0;
+                flat_map(&mut self.children, &mut |node| {
+                    if !predicate(&node) {
+                        return vec![node];
+                    }
+                    count += 1;
+                    let mut wrapper = wrapper.clone();
+                    wrapper.children.push(node);
+                    vec![Node::Element(wrapper)]
+                });
+                count
+            }
+
+            /// Inserts a copy of `new_node` before all nodes, at any depth, that match the
+            /// predicate, returning the number of inserted nodes
+            pub fn insert_before_where<F>(&mut self, mut predicate: F, new_node: &Node<'a>) -> usize
+            where
+                F: FnMut(&Node<'a>) -> bool,
+            {
+                let mut count = 0;
+                flat_map(&mut self.children, &mut |node| {
+                    if !predicate(&node) {
+                        return vec![node];
+                    }
+                    count += 1;
+                    vec![new_node.clone(), node]
+                });
+                count
+            }
+
+            /// Inserts a copy of `new_node` after all nodes, at any depth, that match the
+            /// predicate, returning the number of inserted nodes
+            pub fn insert_after_where<F>(&mut self, mut predicate: F, new_node: &Node<'a>) -> usize
+            where
+                F: FnMut(&Node<'a>) -> bool,
+            {
+                let mut count = 0;
+                flat_map(&mut self.children, &mut |node| {
+                    if !predicate(&node) {
+                        return vec![node];
+                    }
+                    count += 1;
+                    vec![node, new_node.clone()]
+                });
+                count
+            }
+
+            /// Replaces every node, at any depth, with the nodes returned by `f`, which can be
+            /// none, the node itself or any number of new nodes. This is the most general form of
+            /// the methods above
+            pub fn replace_with<F>(&mut self, mut f: F)
+            where
+                F: FnMut(Node<'a>) -> Vec<Node<'a>>,
+            {
+                flat_map(&mut self.children, &mut f)
+            }
+
+            /// Visits all nodes, at any depth, in document order with mutable access.
+            ///
+            /// This is the mutable counterpart to iterating over a [`Node`]. A callback is used
+            /// instead of an iterator, as a node and its children can't be borrowed mutably at the
+            /// same time. Children that are changed by the callback are visited afterwards.
+            pub fn walk_mut<F>(&mut self, mut f: F)
+            where
+                F: FnMut(&mut Node<'a>),
+            {
+                walk_mut(&mut self.children, &mut f)
+            }
+        }
+    };
+}
+
+impl_manipulation!(Dom<'a>);
+impl_manipulation!(Element<'a>);
+
+impl<'a> Node<'a> {
+    /// Visits the node and all of its descendants in document order with mutable access, see
+    /// [`Dom::walk_mut`].
+    pub fn walk_mut<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&mut Node<'a>),
+    {
+        f(self);
+        if let Some(children) = self.children_mut() {
+            walk_mut(children, &mut f);
+        }
+    }
+}
+
+impl<'a> Element<'a> {
+    /// Sets an attribute, where `id` and `class` update `Element::id` and `Element::classes`, and
+    /// replace any repeated `id` or `class` attributes
+    pub fn set_attribute(&mut self, key: &str, value: Option<&str>) {
+        match key.to_ascii_lowercase().as_str() {
+            "id" => {
+                self.remove_attributes(key);
+                self.id = value.map(|value| Cow::Owned(value.to_string()));
+            }
+            "class" => {
+                self.remove_attributes(key);
+                self.classes = value
+                    .unwrap_or_default()
+                    .split_whitespace()
+                    .map(|class| Cow::Owned(class.to_string()))
+                    .collect()
+            }
+            _ => {
+                self.attributes.insert(
+                    key.to_string(),
+                    value.map(|value| Cow::Owned(value.to_string())),
+                );
+            }
+        }
+    }
+
+    /// Removes an attribute, including any duplicates, and returns the previous value. `id` and
+    /// `class` are removed from `Element::id` and `Element::classes`, as well as any repeated
+    /// `id` or `class` attributes.
+    pub fn remove_attribute(&mut self, key: &str) -> Option<Option<Cow<'a, str>>> {
+        let removed = self.remove_attributes(key);
+        match key.to_ascii_lowercase().as_str() {
+            "id" => self.id.take().map(Some).or(removed),
+            "class" if self.classes.is_empty() => removed,
+            "class" => Some(Some(Cow::Owned(
+                std::mem::take(&mut self.classes).join(" "),
+            ))),
+            _ => removed,
+        }
+    }
+
+    /// Removes all attributes with the key. The positions of the id and class count all
+    /// attributes of the start tag, so they move along.
+    fn remove_attributes(&mut self, key: &str) -> Option<Option<Cow<'a, str>>> {
+        let SplitPositions {
+            id: id_position,
+            class: class_position,
+        } = self.split_positions;
+        let attributes = &self.attributes;
+        let shift = |position: Option<usize>, other: Option<usize>| {
+            position.map(|position| {
+                let other_before = other.is_some_and(|other| other < position);
+                let removed = attributes
+                    .iter()
+                    .take(position - usize::from(other_before))
+                    .filter(|attribute| attribute.key.eq_ignore_ascii_case(key))
+                    .count();
+                position - removed
+            })
+        };
+        self.split_positions = SplitPositions {
+            id: shift(id_position, class_position),
+            class: shift(class_position, id_position),
+        };
+        self.attributes.remove(key)
+    }
+
+    pub fn has_class(&self, class: &str) -> bool {
+        self.classes.iter().any(|x| x == class)
+    }
+
+    /// Adds a class, unless the element already has it
+    pub fn add_class(&mut self, class: &str) {
+        if !self.has_class(class) {
+            self.classes.push(Cow::Owned(class.to_string()));
+        }
+    }
+
+    /// Removes a class, returning true if the element had it
+    pub fn remove_class(&mut self, class: &str) -> bool {
+        let length = self.classes.len();
+        self.classes.retain(|x| x != class);
+        self.classes.len() != length
+    }
+}
+
+// The helpers walk the tree with an explicit stack instead of recursion, so that deep trees
+// can't overflow the stack.
+
+fn remove_where<'a, F>(nodes: &mut Vec<Node<'a>>, predicate: &mut F) -> usize
+where
+    F: FnMut(&Node<'a>) -> bool,
+{
+    let mut count = 0;
+    let mut levels = vec![nodes];
+    while let Some(nodes) = levels.pop() {
+        let length = nodes.len();
+        nodes.retain(|node| !predicate(node));
+        count += length - nodes.len();
+        // Reversed, so that the first node is on top of the stack
+        levels.extend(
+            nodes
+                .iter_mut()
+                .rev()
+                .filter_map(|node| node.children_mut()),
+        );
+    }
+    count
+}
+
+fn flat_map<'a, F>(nodes: &mut Vec<Node<'a>>, f: &mut F)
+where
+    F: FnMut(Node<'a>) -> Vec<Node<'a>>,
+{
+    // Every level has the nodes that are left, the nodes they were mapped to and the node they
+    // are the children of, which is mapped after all of them
+    let mut levels = vec![(std::mem::take(nodes).into_iter(), vec![], None)];
+    while let Some((remaining, mapped, _)) = levels.last_mut() {
+        if let Some(mut node) = remaining.next() {
+            match node.children_mut() {
+                Some(children) => {
+                    let children = std::mem::take(children).into_iter();
+                    levels.push((children, vec![], Some(node)));
+                }
+                None => mapped.extend(f(node)),
+            }
+        } else if let Some((_, children, parent)) = levels.pop() {
+            match (parent, levels.last_mut()) {
+                (Some(mut parent), Some((_, mapped, _))) => {
+                    if let Some(parent_children) = parent.children_mut() {
+                        *parent_children = children;
+                    }
+                    mapped.extend(f(parent));
+                }
+                _ => *nodes = children,
+            }
+        }
+    }
+}
+
+fn walk_mut<'a, F>(nodes: &mut [Node<'a>], f: &mut F)
+where
+    F: FnMut(&mut Node<'a>),
+{
+    let mut levels = vec![nodes.iter_mut()];
+    while let Some(level) = levels.last_mut() {
+        match level.next() {
+            Some(node) => {
+                f(node);
+                if let Some(children) = node.children_mut() {
+                    levels.push(children.iter_mut());
+                }
+            }
+            None => {
+                levels.pop();
+            }
+        }
+    }
+}
@@ -1,23 +1,41 @@
 use crate::Result;
-use pest::{iterators::Pair, iterators::Pairs, Parser};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::cell::OnceCell;
 use std::default::Default;
+use std::time::Instant;
 
-use crate::error::Error;
-use crate::grammar::Grammar;
-use crate::Rule;
+use crate::error::{Error, Limit, ParseError, ParseErrorKind, Severity};
+use crate::selector::Selector;
+use crate::tokenizer::{Lexer, Span, Tag, TagAttribute, Token};
 
+pub mod arena;
+pub mod attribute;
+mod builder;
+pub mod doctype;
 pub mod element;
+mod entity;
+pub mod events;
 pub mod formatting;
+mod html;
+mod json;
+mod manipulation;
 pub mod node;
+pub mod options;
 pub mod span;
 
-use crate::dom::span::SourceSpan;
-use element::{Element, ElementVariant};
-use node::Node;
+use crate::dom::span::{LineIndex, SourceSpan};
+use arena::ArenaDom;
+use attribute::{Attribute, Attributes};
+use builder::TreeBuilder;
+use doctype::{Doctype, QuirksMode};
+use element::Element;
+use events::{Event, ReadError, Reader};
+use node::{Cdata, Comment, Node, ProcessingInstruction, Text};
+use options::{CdataSections, JsonOptions, ParseOptions, TagCase, Tokenizer};
 
 /// Document, DocumentFragment or Empty
-#[derive(Debug, Clone, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub enum DomVariant {
     /// This means that the parsed html had the representation of an html document. The doctype is optional but a document should only have one root node with the name of html.
@@ -42,39 +60,205 @@ pub enum DomVariant {
     Empty,
 }
 
-/// **The main struct** & the result of the parsed html
-#[derive(Debug, Clone, Serialize, PartialEq)]
+/// **The main struct** & the result of the parsed html.
+///
+/// The tree borrows text, names and attributes from the parsed input wherever they didn't have to
+/// be decoded, and [`Dom::into_owned`] detaches it from the input.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
-pub struct Dom {
+pub struct Dom<'a> {
     /// The type of the tree that was parsed
     pub tree_type: DomVariant,
 
     /// All of the root children in the tree
-    #[serde(skip_serializing_if = "Vec::is_empty")]
-    pub children: Vec<Node>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<Node<'a>>,
 
-    /// A collection of all errors during parsing
-    #[serde(skip_serializing)]
-    pub errors: Vec<String>,
+    /// A collection of all errors during parsing, that didn't stop the parsing
+    #[serde(skip_serializing, default)]
+    pub errors: Vec<ParseError>,
+
+    /// The parsed input, which source spans refer to. Empty if source spans were disabled
+    #[serde(skip)]
+    pub source: Cow<'a, str>,
 }
 
-impl Default for Dom {
+impl Default for Dom<'_> {
     fn default() -> Self {
         Self {
             tree_type: DomVariant::Empty,
             children: vec![],
             errors: vec![],
+            source: Cow::Borrowed(""),
         }
     }
 }
 
-impl Dom {
-    pub fn parse(input: &str) -> Result<Self> {
-        let pairs = match Grammar::parse(Rule::html, input) {
-            Ok(pairs) => pairs,
-            Err(error) => return formatting::error_msg(error),
+/// How errors are handled, which is selected by the parse function
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Mode {
+    /// Syntax and validation errors stop the parsing
+    Default,
+    /// The input always matches the grammar and validation errors are collected, see
+    /// [`Dom::parse_lenient`]
+    Lenient,
+    /// Every error stops the parsing, see [`Dom::parse_strict`]
+    Strict,
+}
+
+/// State that is shared while the tree is built
+struct Context<'a> {
+    input: &'a str,
+    options: ParseOptions,
+    /// Built on the first span, which is the first error if source spans are disabled
+    line_index: OnceCell<LineIndex<'a>>,
+    mode: Mode,
+    /// When the [`ParseOptions::timeout`] runs out
+    deadline: Option<Instant>,
+}
+
+impl<'a> Context<'a> {
+    fn new(input: &'a str, options: &ParseOptions, mode: Mode) -> Self {
+        Self {
+            input,
+            options: options.clone(),
+            line_index: OnceCell::new(),
+            mode,
+            deadline: options
+                .timeout
+                .and_then(|timeout| Instant::now().checked_add(timeout)),
+        }
+    }
+
+    fn line_index(&self) -> &LineIndex<'a> {
+        self.line_index.get_or_init(|| LineIndex::new(self.input))
+    }
+
+    /// Errors always have a position, even if source spans are disabled
+    fn error_span(&self, span: Span) -> SourceSpan {
+        self.line_index().span(span.start(), span.end())
+    }
+}
+
+impl<'a> Dom<'a> {
+    pub fn parse(input: &'a str) -> Result<Self> {
+        Self::parse_with(input, &ParseOptions::default())
+    }
+
+    /// Parses the input with the given options, see [`ParseOptions`].
+    pub fn parse_with(input: &'a str, options: &ParseOptions) -> Result<Self> {
+        Self::parse_in_mode(input, options, Mode::Default)
+    }
+
+    /// Parses the input like [`Dom::parse`], but every error that would be collected in
+    /// [`Dom::errors`], even a warning, stops the parsing with the position of the first one.
+    /// This includes dangling end tags that are discarded, duplicate attributes and self-closing
+    /// tags of non-void elements, like `<div/>`, which are only reported in strict mode.
+    ///
+    /// ```rust
+    /// use html_parser::{Dom, Error};
+    ///
+    /// assert!(Dom::parse_strict("<p>one</p><p>two</p>").is_ok());
+    /// match Dom::parse_strict("<p>one</p></div>") {
+    ///     Err(Error::Parsing(error)) => assert_eq!(error.span.start, 10),
+    ///     _ => unreachable!(),
+    /// }
+    /// ```
+    pub fn parse_strict(input: &'a str) -> Result<Self> {
+        Self::parse_strict_with(input, &ParseOptions::default())
+    }
+
+    /// Parses the input with the given options, see [`Dom::parse_strict`].
+    pub fn parse_strict_with(input: &'a str, options: &ParseOptions) -> Result<Self> {
+        Self::parse_in_mode(input, options, Mode::Strict)
+    }
+
+    /// Parses any input into a tree, where [`Dom::parse`] would fail. Markup that can't be
+    /// parsed, like `<!x>` or a comment without an end, is kept as a comment, and the errors that
+    /// would stop the parsing are collected in [`Dom::errors`] instead.
+    ///
+    /// ```rust
+    /// use html_parser::Dom;
+    ///
+    /// let dom = Dom::parse_lenient("<p>one</p><!x><p>two</p>");
+    /// assert_eq!(dom.to_html(), "<p>one</p><!--x--><p>two</p>");
+    /// assert_eq!(dom.errors.len(), 1);
+    /// ```
+    pub fn parse_lenient(input: &'a str) -> Self {
+        Self::parse_lenient_with(input, &ParseOptions::default())
+    }
+
+    /// Parses any input with the given options, see [`Dom::parse_lenient`].
+    pub fn parse_lenient_with(input: &'a str, options: &ParseOptions) -> Self {
+        // The lenient grammar matches any input and limits are collected as errors, so this is
+        // only a safeguard
+        Self::parse_in_mode(input, options, Mode::Lenient).unwrap_or_else(|error| match error {
+            Error::Parsing(error) => Self {
+                errors: vec![error],
+                ..Self::default()
+            },
+            error => unreachable!("[parse lenient] {}", error),
+        })
+    }
+
+    fn parse_in_mode(input: &'a str, options: &ParseOptions, mode: Mode) -> Result<Self> {
+        let lenient = mode == Mode::Lenient;
+
+        // In lenient mode only the input up to the limit is parsed
+        let mut too_large = None;
+        let input = match options.max_input_size {
+            Some(max) if input.len() > max && !lenient => {
+                return Err(Error::LimitExceeded(Limit::InputSize(max)))
+            }
+            Some(max) if input.len() > max => {
+                let end = (0..=max)
+                    .rev()
+                    .find(|&end| input.is_char_boundary(end))
+                    .unwrap_or_default();
+                too_large = Some(Limit::InputSize(max));
+                &input[..end]
+            }
+            _ => input,
         };
-        Self::build_dom(pairs)
+
+        let context = Context::new(input, options, mode);
+        let mut dom = match options.tokenizer {
+            Tokenizer::Streaming => Self::build_dom(input, Lexer::new(input, lenient), &context)?,
+            #[cfg(feature = "pest")]
+            Tokenizer::Pest => {
+                let tokens = crate::grammar::tokens(input, lenient)?;
+                Self::build_dom(input, tokens, &context)?
+            }
+        };
+        if let Some(limit) = too_large {
+            let end = Span::new(input, input.len(), input.len());
+            dom.errors.push(ParseError::new(
+                ParseErrorKind::LimitExceeded,
+                limit.to_string(),
+                context.error_span(end),
+            ));
+        }
+        if options.source_spans {
+            dom.source = Cow::Borrowed(input);
+        }
+        Ok(dom)
+    }
+
+    /// Reads a tree from json, as written by [`Dom::to_json`] or [`Dom::to_json_with`] with any
+    /// of the [`JsonSchema`](options::JsonSchema)s.
+    ///
+    /// With the untagged schema, comments can't be told apart from text and are read as text.
+    /// Source spans are kept if they are in the json, but [`Dom::source`] is empty. Trees that are
+    /// nested deeper than with the default [`ParseOptions::max_depth`], 512 elements and the text
+    /// in the innermost one, are an error.
+    pub fn from_json(json: &str) -> Result<Self> {
+        // The depth of the tree is limited as in `Node`'s `Deserialize` instead of by the
+        // recursion limit of serde_json, which is too low for it, with a stack that grows as needed
+        let mut deserializer = serde_json::Deserializer::from_str(json);
+        deserializer.disable_recursion_limit();
+        let dom = Self::deserialize(serde_stacker::Deserializer::new(&mut deserializer))?;
+        deserializer.end()?;
+        Ok(dom)
     }
 
     pub fn to_json(&self) -> Result<String> {
@@ -85,80 +269,130 @@ impl Dom {
         Ok(serde_json::to_string_pretty(self)?)
     }
 
-    fn build_dom(pairs: Pairs<Rule>) -> Result<Self> {
-        let mut dom = Self::default();
-
-        // NOTE: The logic is roughly as follows:
-        // 1) A document containing nothing but comments is DomVariant::Empty even though it will have
-        //    children in this first pass.  We fix this in the next section.  This allows us to use
-        //    DomVariant::Empty to indicate "we haven't decided the type yet".
-        // 2) If the type is DomVariant::Empty _so far_, then it can be changed to DomVariant::Document
-        //    or DomVariant::DocumentFragment.  DomVariant is only selected in this stage if we see a
-        //    DOCTYPE tag.  Comments do not change the type.
-        // 3) If the type is non-empty, we don't re-set the type.  We do look for conflicts between
-        //    the type and the tokens in the next stage.
-        for pair in pairs {
-            match pair.as_rule() {
-                // A <!DOCTYPE> tag means a full-fledged document.  Note that because of the way
-                // the grammar is written, we will only get this token if the <!DOCTYPE> occurs
-                // before any other tag; otherwise it will be parsed as a custom tag.
-                Rule::doctype => {
-                    if dom.tree_type == DomVariant::Empty {
-                        dom.tree_type = DomVariant::Document;
-                    }
-                }
+    /// Serializes the tree to json with the schema and extra fields that are selected by the
+    /// options, see [`JsonOptions`].
+    pub fn to_json_with(&self, options: &JsonOptions) -> Result<String> {
+        json::to_string(&json::Json::new(self, options), options)
+    }
 
-                // If we see an element, build the sub-tree and add it as a child.  If we don't
-                // have a document type yet (i.e. "empty"), select DocumentFragment
-                Rule::node_element => match Self::build_node_element(pair, &mut dom) {
-                    Ok(el) => {
-                        if let Some(node) = el {
-                            if dom.tree_type == DomVariant::Empty {
-                                dom.tree_type = DomVariant::DocumentFragment;
-                            };
-                            dom.children.push(node);
-                        }
-                    }
-                    Err(error) => {
-                        dom.errors.push(format!("{}", error));
-                    }
-                },
+    /// Returns all elements in the tree that match the css selector, in document order.
+    ///
+    /// ```rust
+    /// use html_parser::{Dom, Result};
+    ///
+    /// fn main() -> Result<()> {
+    ///     let dom = Dom::parse("<div class='card'><a href='https://a.com'>a</a><a href='/b'>b</a></div>")?;
+    ///     let links = dom.select("div.card > a[href^=http]")?;
+    ///     assert_eq!(links.len(), 1);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn select(&self, selector: &str) -> Result<Vec<&Element<'_>>> {
+        Ok(Selector::parse(selector)?.select(&self.children))
+    }
 
-                // Similar to an element, we add it as a child and select DocumentFragment if we
-                // don't already have a document type.
-                Rule::node_text => {
-                    if dom.tree_type == DomVariant::Empty {
-                        dom.tree_type = DomVariant::DocumentFragment;
-                    }
-                    let text = pair.as_str().to_string();
-                    if !text.trim().is_empty() {
-                        dom.children.push(Node::Text(text));
-                    }
-                }
+    /// The text of a span in the parsed input, see [`SourceSpan`]
+    pub fn span_text(&self, span: &SourceSpan) -> &str {
+        span.text(&self.source)
+    }
 
-                // Store comments as a child, but it doesn't affect the document type selection
-                // until the next phase (validation).
-                Rule::node_comment => {
-                    dom.children
-                        .push(Node::Comment(pair.into_inner().as_str().to_string()));
-                }
+    /// The doctype of the document, if there is one
+    pub fn doctype(&self) -> Option<&Doctype> {
+        self.children.iter().find_map(Node::doctype)
+    }
+
+    /// The quirks mode that browsers would render the document in, which is selected by the
+    /// doctype. Documents without a doctype are in quirks mode, fragments are never.
+    pub fn quirks_mode(&self) -> QuirksMode {
+        match (self.doctype(), &self.tree_type) {
+            (Some(doctype), _) => doctype.quirks_mode(),
+            (None, DomVariant::Document) => QuirksMode::Quirks,
+            (None, _) => QuirksMode::NoQuirks,
+        }
+    }
+
+    /// Detaches the tree from the parsed input, so that it can outlive it.
+    ///
+    /// ```rust
+    /// use html_parser::{Dom, Result};
+    ///
+    /// fn parse_file(path: &str) -> Result<Dom<'static>> {
+    ///     let html = std::fs::read_to_string(path)?;
+    ///     Ok(Dom::parse(&html)?.into_owned())
+    /// }
+    /// ```
+    pub fn into_owned(self) -> Dom<'static> {
+        Dom {
+            tree_type: self.tree_type,
+            children: self.children.into_iter().map(Node::into_owned).collect(),
+            errors: self.errors,
+            source: Cow::Owned(self.source.into_owned()),
+        }
+    }
+
+    /// Converts the tree into an [`ArenaDom`], with parent and sibling navigation
+    pub fn into_arena(self) -> ArenaDom<'a> {
+        ArenaDom::from_dom(self)
+    }
 
-                // Ignore 'end of input', which then allows the catch-all unreachable!() arm to
-                // function properly.
-                Rule::EOI => (),
+    /// Serializes the tree back to html. This is the same as using the `Display` implementation.
+    pub fn to_html(&self) -> String {
+        self.to_string()
+    }
 
-                // This should be unreachable, due to the way the grammar is written
-                _ => unreachable!("[build dom] unknown rule: {:?}", pair.as_rule()),
+    fn build_dom(
+        input: &'a str,
+        tokens: impl Iterator<Item = std::result::Result<Token<'a>, ParseError>>,
+        context: &Context<'a>,
+    ) -> Result<Self> {
+        // The events of the tokens are built into a tree by the `TreeBuilder`, which also selects
+        // the type: a doctype at the root makes a `DomVariant::Document`, and other content at the
+        // root a `DomVariant::DocumentFragment`, while comments leave it `DomVariant::Empty`. The
+        // type is checked, and may still change, once the tree is built. The first limit that is
+        // exceeded stops the events.
+        let mut builder = TreeBuilder::new(input, context);
+        let mut reader = Reader::new(tokens);
+        let mut exceeded = None;
+        while let Some(event) = reader.next(context, builder.errors()) {
+            let limit = match event {
+                Ok(event) => {
+                    let span = event.span();
+                    builder.event(event);
+                    context
+                        .options
+                        .max_nodes
+                        .filter(|&max| builder.nodes() > max)
+                        .map(|max| (Limit::Nodes(max), span.trim()))
+                }
+                Err(ReadError::Parsing(error)) => return Err(error.into()),
+                Err(ReadError::Limit(limit, span)) => Some((limit, span)),
             };
+            if let Some((limit, span)) = limit {
+                if context.mode != Mode::Lenient {
+                    return Err(Error::LimitExceeded(limit));
+                }
+                exceeded = Some(ParseError::new(
+                    ParseErrorKind::LimitExceeded,
+                    limit.to_string(),
+                    context.error_span(span),
+                ));
+                break;
+            }
         }
+        let mut dom = builder.finish();
+        let mut errors = vec![];
 
         // Implement some checks on the generated dom's data and initial type.  The type may be
         // modified in this section.
         match dom.tree_type {
-            // A DomVariant::Empty can only have comments. Anything else is an error.
+            // A DomVariant::Empty can only have comments and processing instructions. Anything
+            // else is an error.
             DomVariant::Empty => {
                 for node in &dom.children {
-                    if let Node::Comment(_) = node {
+                    if let Node::Comment(_)
+                    | Node::ConditionalComment(_)
+                    | Node::ProcessingInstruction(_) = node
+                    {
                         // An "empty" document, but it has comments - this is where we cleanup the
                         // earlier assumption that a document with only comments is "empty".
                         // Really, it is a "fragment".
@@ -174,17 +408,17 @@ impl Dom {
             // A DomVariant::Document can only have comments and an <HTML> node at the top level.
             // Only one <HTML> tag is permitted.
             DomVariant::Document => {
-                if dom
+                let html_elements = dom
                     .children
                     .iter()
-                    .filter(|x| match x {
-                        Node::Element(el) if el.name.to_lowercase() == "html" => true,
-                        _ => false,
-                    })
-                    .count()
-                    > 1
-                {
-                    return Err(Error::Parsing(format!("Document with multiple HTML tags",)));
+                    .filter_map(|x| x.element())
+                    .filter(|el| el.name.to_lowercase() == "html");
+                for html in html_elements.skip(1) {
+                    errors.push(ParseError::new(
+                        ParseErrorKind::MultipleHtmlElements,
+                        "Document with multiple HTML tags",
+                        html.source_span.clone(),
+                    ));
                 }
             }
 
@@ -200,20 +434,23 @@ impl Dom {
                         // Nodes other than <HTML> - reject <HEAD> and <BODY>
                         Node::Element(ref el) if el.name.clone().to_lowercase() != "html" => {
                             if el.name == "head" || el.name == "body" {
-                                return Err(Error::Parsing(format!(
-                                    "A document fragment should not include {}",
-                                    el.name
-                                )));
+                                errors.push(ParseError::new(
+                                    ParseErrorKind::InvalidFragment,
+                                    format!("A document fragment should not include {}", el.name),
+                                    el.source_span.clone(),
+                                ));
                             }
                             seen_elements = true;
                         }
                         // <HTML> Nodes - one (before any other elements) is okay
                         Node::Element(ref el) if el.name.clone().to_lowercase() == "html" => {
                             if seen_html || seen_elements {
-                                return Err(Error::Parsing(format!(
-                                    "A document fragment should not include {}",
-                                    el.name
-                                )));
+                                errors.push(ParseError::new(
+                                    ParseErrorKind::InvalidFragment,
+                                    format!("A document fragment should not include {}", el.name),
+                                    el.source_span.clone(),
+                                ));
+                                continue;
                             };
 
                             // A fragment with just an <HTML> tag is a document
@@ -228,138 +465,213 @@ impl Dom {
             }
         }
 
-        // The result is the validated tree
-        Ok(dom)
+        // Validation errors stop the parsing, unless they are only collected in lenient mode
+        match context.mode {
+            Mode::Default | Mode::Strict if !errors.is_empty() => Err(errors.remove(0).into()),
+            // In strict mode the first error in the input stops the parsing, even a warning
+            Mode::Strict if !dom.errors.is_empty() => {
+                let index = (0..dom.errors.len())
+                    .min_by_key(|&index| dom.errors[index].span.start)
+                    .unwrap_or_default();
+                Err(ParseError {
+                    severity: Severity::Error,
+                    ..dom.errors.swap_remove(index)
+                }
+                .into())
+            }
+            _ => {
+                dom.errors.append(&mut errors);
+                dom.errors.extend(exceeded);
+                Ok(dom)
+            }
+        }
     }
 
-    fn build_node_element(pair: Pair<Rule>, dom: &mut Dom) -> Result<Option<Node>> {
-        let source_span = {
-            let pair_span = pair.as_span();
-            let (start_line, start_column) = pair_span.start_pos().line_col();
-            let (end_line, end_column) = pair_span.end_pos().line_col();
+    /// Builds the event of a start tag, where repeated attributes are reported in strict mode
+    fn build_start_tag(tag: Tag<'a>, errors: &mut Vec<ParseError>, context: &Context) -> Event<'a> {
+        let mut attributes = Attributes::new();
+        let mut keys: Vec<Cow<str>> = vec![];
+        for attribute in tag.attributes {
+            let span = attribute.span.trim();
+            let attribute = Self::build_attribute(attribute, context);
+            let duplicate = keys
+                .iter()
+                .any(|key| key.eq_ignore_ascii_case(&attribute.key));
+            if duplicate && context.mode == Mode::Strict {
+                errors.push(ParseError {
+                    severity: Severity::Warning,
+                    ..ParseError::new(
+                        ParseErrorKind::DuplicateAttribute,
+                        format!("Duplicate attribute: {}", attribute.key),
+                        context.error_span(span),
+                    )
+                });
+            } else if !duplicate {
+                keys.push(attribute.key.clone());
+            }
+            attributes.push(attribute);
+        }
+        Event::StartTag {
+            name: Self::build_name(tag.name, context),
+            attributes,
+            self_closing: tag.self_closing,
+            // The start tag of raw text elements includes the whitespace after it
+            span: Self::build_source_span(tag.span.trim(), context),
+        }
+    }
 
-            SourceSpan::new(
-                String::from(pair_span.as_str()),
-                start_line,
-                end_line,
-                start_column,
-                end_column,
-            )
-        };
+    /// The name of an element in the case of the options
+    fn build_name(name: &'a str, context: &Context) -> Cow<'a, str> {
+        // TODO: To enable some kind of validation we should probably align this with
+        // https://html.spec.whatwg.org/multipage/syntax.html#elements-2
+        // Also see element variants
+        match context.options.tag_case {
+            TagCase::Lowercase if name.bytes().any(|x| x.is_ascii_uppercase()) => {
+                Cow::Owned(name.to_lowercase())
+            }
+            _ => Cow::Borrowed(name),
+        }
+    }
 
+    /// Builds an element from the event of its start tag. The element is void until it's closed
+    /// by the tree builder.
+    fn build_element(
+        name: Cow<'a, str>,
+        attributes: Attributes<'a>,
+        span: SourceSpan,
+        context: &Context,
+    ) -> Element<'a> {
         let mut element = Element {
-            source_span,
+            name,
+            source_span: span.clone(),
+            start_tag_span: span,
             ..Element::default()
         };
-
-        for pair in pair.into_inner() {
-            match pair.as_rule() {
-                Rule::node_element | Rule::el_raw_text => {
-                    match Self::build_node_element(pair, dom) {
-                        Ok(el) => {
-                            if let Some(child_element) = el {
-                                element.children.push(child_element)
-                            }
-                        }
-                        Err(error) => {
-                            dom.errors.push(format!("{}", error));
-                        }
-                    }
-                }
-                Rule::node_text | Rule::el_raw_text_content => {
-                    let text = pair.as_str().to_string();
-                    if !text.trim().is_empty() {
-                        element.children.push(Node::Text(text));
-                    }
-                }
-                Rule::node_comment => {
-                    element
-                        .children
-                        .push(Node::Comment(pair.into_inner().as_str().to_string()));
-                }
-                // TODO: To enable some kind of validation we should probably align this with
-                // https://html.spec.whatwg.org/multipage/syntax.html#elements-2
-                // Also see element variants
-                Rule::el_name | Rule::el_void_name | Rule::el_raw_text_name => {
-                    element.name = pair.as_str().to_string();
-                }
-                Rule::attr => match Self::build_attribute(pair.into_inner()) {
-                    Ok((attr_key, attr_value)) => {
-                        match attr_key.as_str() {
-                            "id" => element.id = attr_value,
-                            "class" => {
-                                if let Some(classes) = attr_value {
-                                    let classes = classes.split_whitespace().collect::<Vec<_>>();
-                                    for class in classes {
-                                        element.classes.push(class.to_string());
-                                    }
-                                }
-                            }
-                            _ => {
-                                element.attributes.insert(attr_key, attr_value);
-                            }
-                        };
-                    }
-                    Err(error) => {
-                        dom.errors.push(format!("{}", error));
-                    }
-                },
-                Rule::el_normal_end | Rule::el_raw_text_end => {
-                    element.variant = ElementVariant::Normal;
-                    break;
-                }
-                Rule::el_dangling => (),
-                Rule::EOI => (),
-                _ => {
-                    return Err(Error::Parsing(format!(
-                        "Failed to create element at rule: {:?}",
-                        pair.as_rule()
-                    )))
+        // Only the first id and class attributes are used, as in browsers
+        let mut has_id = false;
+        let mut has_class = false;
+        for (index, attribute) in attributes.into_iter().enumerate() {
+            let key = attribute.key.as_ref();
+            let split = context.options.split_id_and_class;
+            if split && !has_id && key.eq_ignore_ascii_case("id") {
+                has_id = true;
+                element.id = attribute.value;
+                element.split_positions.id = Some(index);
+            } else if split && !has_class && key.eq_ignore_ascii_case("class") {
+                has_class = true;
+                element.split_positions.class = Some(index);
+                // Classes borrow from the input, unless the value was decoded
+                match attribute.value {
+                    Some(Cow::Borrowed(classes)) => element
+                        .classes
+                        .extend(classes.split_whitespace().map(Cow::Borrowed)),
+                    Some(Cow::Owned(classes)) => element.classes.extend(
+                        classes
+                            .split_whitespace()
+                            .map(|class| Cow::Owned(class.to_string())),
+                    ),
+                    None => (),
                 }
+            } else {
+                element.attributes.push(attribute);
             }
         }
-        if element.name != "" {
-            Ok(Some(Node::Element(element)))
+        element
+    }
+
+    fn build_attribute(attribute: TagAttribute<'a>, context: &Context) -> Attribute<'a> {
+        // The attribute and its key include the whitespace around the key, which isn't part of
+        // the span
+        let key = attribute.key.trim();
+        Attribute {
+            key: Cow::Borrowed(key.as_str()),
+            key_span: Self::build_source_span(key, context),
+            value: attribute
+                .value
+                .map(|value| Self::build_attribute_value(value.as_str(), context)),
+            value_span: attribute
+                .value
+                .map(|value| Self::build_source_span(value, context)),
+            source_span: Self::build_source_span(attribute.span.trim(), context),
+        }
+    }
+
+    fn build_source_span(span: Span, context: &Context) -> SourceSpan {
+        if context.options.source_spans {
+            context.line_index().span(span.start(), span.end())
         } else {
-            Ok(None)
+            SourceSpan::default()
         }
     }
 
-    fn build_attribute(pairs: Pairs<Rule>) -> Result<(String, Option<String>)> {
-        let mut attribute = ("".to_string(), None);
-        for pair in pairs {
-            match pair.as_rule() {
-                Rule::attr_key => {
-                    attribute.0 = pair.as_str().trim().to_string();
-                }
-                Rule::attr_non_quoted => {
-                    attribute.1 = Some(pair.as_str().trim().to_string());
-                }
-                Rule::attr_quoted => {
-                    let inner_pair = pair
-                        .into_inner()
-                        .into_iter()
-                        .next()
-                        .expect("attribute value");
-
-                    match inner_pair.as_rule() {
-                        Rule::attr_value => attribute.1 = Some(inner_pair.as_str().to_string()),
-                        _ => {
-                            return Err(Error::Parsing(format!(
-                                "Failed to parse attr value: {:?}",
-                                inner_pair.as_rule()
-                            )))
-                        }
-                    }
-                }
-                _ => {
-                    return Err(Error::Parsing(format!(
-                        "Failed to parse attr: {:?}",
-                        pair.as_rule()
-                    )))
-                }
-            }
+    /// Text that is only whitespace is left out, unless the options say otherwise
+    fn keep_text(text: &str, context: &Context) -> bool {
+        context.options.keep_whitespace_text || !text.trim().is_empty()
+    }
+
+    fn build_text(span: Span<'a>, decode: bool, context: &Context) -> Text<'a> {
+        let text = span.as_str();
+        let value = if decode && context.options.decode_entities {
+            entity::decode(text, false)
+        } else {
+            Cow::Borrowed(text)
+        };
+        Text {
+            value,
+            source_span: Self::build_source_span(span, context),
+        }
+    }
+
+    fn build_doctype(span: Span, content: &str, context: &Context) -> Doctype {
+        Doctype {
+            source_span: Self::build_source_span(span.trim(), context),
+            ..Doctype::parse(content)
+        }
+    }
+
+    fn build_processing_instruction(
+        span: Span,
+        target: &'a str,
+        data: &'a str,
+        context: &Context,
+    ) -> ProcessingInstruction<'a> {
+        ProcessingInstruction {
+            target: Cow::Borrowed(target),
+            data: Cow::Borrowed(data),
+            source_span: Self::build_source_span(span, context),
+        }
+    }
+
+    /// CDATA sections are only recognized in foreign content and xhtml, elsewhere they are
+    /// comments, as in browsers
+    fn build_cdata(cdata: Cdata<'a>, in_foreign_content: bool, context: &Context) -> Node<'a> {
+        if !in_foreign_content && !context.options.xhtml {
+            return Node::Comment(Comment {
+                value: Cow::Owned(format!("[CDATA[{}]]", cdata.value)),
+                source_span: cdata.source_span,
+            });
+        }
+        match context.options.cdata_sections {
+            CdataSections::Node => Node::Cdata(cdata),
+            CdataSections::Text => Node::Text(Text {
+                value: cdata.value,
+                source_span: cdata.source_span,
+            }),
+        }
+    }
+
+    fn build_comment(span: Span, value: &'a str, context: &Context) -> Comment<'a> {
+        Comment {
+            source_span: Self::build_source_span(span.trim(), context),
+            value: Cow::Borrowed(value),
+        }
+    }
+
+    fn build_attribute_value(value: &'a str, context: &Context) -> Cow<'a, str> {
+        if context.options.decode_entities {
+            entity::decode(value, true)
+        } else {
+            Cow::Borrowed(value)
         }
-        Ok(attribute)
     }
 }
@@ -1,15 +1,365 @@
-use super::element::Element;
-use serde::Serialize;
+use super::attribute::Attributes;
+use super::doctype::Doctype;
+use super::element::{Element, ElementVariant};
+use super::json;
+use super::options::{JsonOptions, DEFAULT_MAX_DEPTH};
+use super::span::SourceSpan;
+use serde::de::{self, value::MapAccessDeserializer, MapAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::Deref;
 
-#[derive(Debug, Clone, Serialize, PartialEq)]
-#[serde(untagged)]
-pub enum Node {
-    Text(String),
-    Element(Element),
-    Comment(String),
+// Elements are kept inline so they can be matched on and moved around without boxing
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Node<'a> {
+    Text(Text<'a>),
+    Element(Element<'a>),
+    Comment(Comment<'a>),
+    Doctype(Doctype),
+    ProcessingInstruction(ProcessingInstruction<'a>),
+    Cdata(Cdata<'a>),
+    ConditionalComment(ConditionalComment<'a>),
 }
 
-impl Node {
+/// An xml processing instruction, ex: `<?xml version="1.0"?>`
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProcessingInstruction<'a> {
+    /// The name after `<?`, ex: `xml`
+    pub target: Cow<'a, str>,
+
+    /// Everything after the target and the whitespace that follows it, up to `?>`, ex:
+    /// `version="1.0"`
+    #[serde(default)]
+    pub data: Cow<'a, str>,
+
+    /// Span in the parsed source
+    #[serde(default)]
+    pub source_span: SourceSpan,
+}
+
+/// A conditional comment of old IE browsers and Outlook, ex: `<!--[if mso]><table></table><![endif]-->`,
+/// where the content is parsed into nodes.
+///
+/// With `downlevel_revealed` the content is visible to browsers that don't support conditional
+/// comments, ex: `<!--[if !mso]><!--><div></div><!--<![endif]-->`.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConditionalComment<'a> {
+    /// The condition between `[if` and `]`, without surrounding whitespace, ex: `gte mso 9`
+    pub condition: Cow<'a, str>,
+
+    #[serde(default)]
+    pub children: Vec<Node<'a>>,
+
+    #[serde(default)]
+    pub downlevel_revealed: bool,
+
+    /// Span in the parsed source
+    #[serde(default)]
+    pub source_span: SourceSpan,
+}
+
+impl ProcessingInstruction<'_> {
+    /// Detaches the instruction from the parsed input, see
+    /// [`Dom::into_owned`](crate::Dom::into_owned)
+    pub fn into_owned(self) -> ProcessingInstruction<'static> {
+        ProcessingInstruction {
+            target: Cow::Owned(self.target.into_owned()),
+            data: Cow::Owned(self.data.into_owned()),
+            source_span: self.source_span,
+        }
+    }
+}
+
+impl ConditionalComment<'_> {
+    /// Detaches the conditional comment, and all of its children, from the parsed input, see
+    /// [`Dom::into_owned`](crate::Dom::into_owned)
+    pub fn into_owned(self) -> ConditionalComment<'static> {
+        ConditionalComment {
+            condition: Cow::Owned(self.condition.into_owned()),
+            children: self.children.into_iter().map(Node::into_owned).collect(),
+            downlevel_revealed: self.downlevel_revealed,
+            source_span: self.source_span,
+        }
+    }
+}
+
+/// Nodes are serialized with the untagged json schema, see [`JsonSchema`](super::options::JsonSchema)
+impl Serialize for Node<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        json::Json::new(self, &JsonOptions::default()).serialize(serializer)
+    }
+}
+
+// Text and comments are strings with a span, that deref to `str` and serialize as plain strings
+macro_rules! string_node {
+    ($(#[$meta:meta])* $type:ident) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Default, PartialEq)]
+        pub struct $type<'a> {
+            pub value: Cow<'a, str>,
+
+            /// Span in the parsed source
+            pub source_span: SourceSpan,
+        }
+
+        impl $type<'_> {
+            pub fn as_str(&self) -> &str {
+                &self.value
+            }
+
+            /// Detaches the value from the parsed input, see [`Dom::into_owned`](crate::Dom::into_owned)
+            pub fn into_owned(self) -> $type<'static> {
+                $type {
+                    value: Cow::Owned(self.value.into_owned()),
+                    source_span: self.source_span,
+                }
+            }
+        }
+
+        impl Deref for $type<'_> {
+            type Target = str;
+
+            fn deref(&self) -> &Self::Target {
+                &self.value
+            }
+        }
+
+        impl fmt::Display for $type<'_> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(&self.value)
+            }
+        }
+
+        impl From<String> for $type<'_> {
+            fn from(value: String) -> Self {
+                Self {
+                    value: Cow::Owned(value),
+                    source_span: SourceSpan::default(),
+                }
+            }
+        }
+
+        impl<'a> From<&'a str> for $type<'a> {
+            fn from(value: &'a str) -> Self {
+                Self {
+                    value: Cow::Borrowed(value),
+                    source_span: SourceSpan::default(),
+                }
+            }
+        }
+
+        impl PartialEq<str> for $type<'_> {
+            fn eq(&self, other: &str) -> bool {
+                self.value == other
+            }
+        }
+
+        impl PartialEq<&str> for $type<'_> {
+            fn eq(&self, other: &&str) -> bool {
+                self.value == *other
+            }
+        }
+
+        impl Serialize for $type<'_> {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(&self.value)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $type<'_> {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                String::deserialize(deserializer).map(Self::from)
+            }
+        }
+    };
+}
+
+string_node!(
+    /// A text node, where the span covers the text as written in the source, before character
+    /// references were decoded
+    Text
+);
+string_node!(
+    /// A comment, where the value is the content between `<!--` and `-->` without surrounding
+    /// whitespace, and the span covers the whole comment
+    Comment
+);
+string_node!(
+    /// A CDATA section in svg, math or xhtml, where the value is the content between
+    /// `<![CDATA[` and `]]>`, and the span covers the whole section
+    Cdata
+);
+
+/// Nodes are read from both json schemas. In the untagged schema text and comments are plain
+/// strings, which are read as text.
+impl<'de, 'a> Deserialize<'de> for Node<'a> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct NodeVisitor<'a>(PhantomData<Node<'a>>);
+
+        impl<'de, 'a> Visitor<'de> for NodeVisitor<'a> {
+            type Value = Node<'a>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a string or a map with the fields of a node")
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+                Ok(Node::Text(value.to_string().into()))
+            }
+
+            fn visit_string<E: de::Error>(self, value: String) -> Result<Self::Value, E> {
+                Ok(Node::Text(value.into()))
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+                let _depth = Depth::enter::<A::Error>()?;
+                Fields::deserialize(MapAccessDeserializer::new(map))?.into_node()
+            }
+        }
+
+        deserializer.deserialize_any(NodeVisitor(PhantomData))
+    }
+}
+
+thread_local! {
+    static DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+/// How deep the node that is being read is nested, which is limited like
+/// [`ParseOptions::max_depth`](super::options::ParseOptions::max_depth) by default. Deeper trees
+/// are rejected before they are built, as the code that walks a tree, like dropping or serializing
+/// it, would overflow the stack.
+struct Depth;
+
+impl Depth {
+    fn enter<E: de::Error>() -> Result<Self, E> {
+        let depth = DEPTH.with(|depth| {
+            depth.set(depth.get() + 1);
+            depth.get()
+        });
+        // Left again when dropped, also on errors
+        let entered = Depth;
+        // The text of the innermost element is a level deeper than the element
+        if depth > DEFAULT_MAX_DEPTH + 1 {
+            return Err(E::custom(format_args!(
+                "nodes nested deeper than {}",
+                DEFAULT_MAX_DEPTH
+            )));
+        }
+        Ok(entered)
+    }
+}
+
+impl Drop for Depth {
+    fn drop(&mut self) {
+        DEPTH.with(|depth| depth.set(depth.get() - 1));
+    }
+}
+
+/// The fields of every kind of node, which are read in one pass. Untagged and internally tagged
+/// enums of serde would buffer each node, and read its content again on every level of the
+/// tree, which takes quadratic time and recurses without bounds.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Fields<'a> {
+    /// The kind of node in the tagged schema, which is an element if it's missing
+    #[serde(rename = "type")]
+    kind: Option<String>,
+    value: Option<Cow<'a, str>>,
+    #[serde(default)]
+    source_span: SourceSpan,
+    id: Option<Cow<'a, str>>,
+    /// The name of an element or a doctype
+    name: Option<Cow<'a, str>>,
+    variant: Option<ElementVariant>,
+    #[serde(default)]
+    attributes: Attributes<'a>,
+    #[serde(default)]
+    classes: Vec<Cow<'a, str>>,
+    #[serde(default)]
+    children: Vec<Node<'a>>,
+    public_id: Option<String>,
+    system_id: Option<String>,
+    #[serde(default)]
+    force_quirks: bool,
+    target: Option<Cow<'a, str>>,
+    #[serde(default)]
+    data: Cow<'a, str>,
+    condition: Option<Cow<'a, str>>,
+    #[serde(default)]
+    downlevel_revealed: bool,
+}
+
+impl<'a> Fields<'a> {
+    fn into_node<E: de::Error>(self) -> Result<Node<'a>, E> {
+        fn required<T, E: de::Error>(field: Option<T>, name: &'static str) -> Result<T, E> {
+            field.ok_or_else(|| E::missing_field(name))
+        }
+
+        let source_span = self.source_span;
+        Ok(match self.kind.as_deref() {
+            None | Some("element") => Node::Element(Element {
+                id: self.id,
+                name: required(self.name, "name")?,
+                variant: required(self.variant, "variant")?,
+                attributes: self.attributes,
+                classes: self.classes,
+                children: self.children,
+                source_span,
+                ..Element::default()
+            }),
+            Some("text") => Node::Text(Text {
+                value: required(self.value, "value")?,
+                source_span,
+            }),
+            Some("comment") => Node::Comment(Comment {
+                value: required(self.value, "value")?,
+                source_span,
+            }),
+            Some("cdata") => Node::Cdata(Cdata {
+                value: required(self.value, "value")?,
+                source_span,
+            }),
+            Some("doctype") => Node::Doctype(Doctype {
+                name: self.name.map(Cow::into_owned),
+                public_id: self.public_id,
+                system_id: self.system_id,
+                force_quirks: self.force_quirks,
+                source_span,
+            }),
+            Some("processingInstruction") => Node::ProcessingInstruction(ProcessingInstruction {
+                target: required(self.target, "target")?,
+                data: self.data,
+                source_span,
+            }),
+            Some("conditionalComment") => Node::ConditionalComment(ConditionalComment {
+                condition: required(self.condition, "condition")?,
+                children: self.children,
+                downlevel_revealed: self.downlevel_revealed,
+                source_span,
+            }),
+            Some(kind) => return Err(E::unknown_variant(kind, NODE_KINDS)),
+        })
+    }
+}
+
+const NODE_KINDS: &[&str] = &[
+    "element",
+    "text",
+    "comment",
+    "cdata",
+    "doctype",
+    "processingInstruction",
+    "conditionalComment",
+];
+
+impl<'a> Node<'a> {
     pub fn text(&self) -> Option<&str> {
         match self {
             Node::Text(t) => Some(t.as_str()),
@@ -17,7 +367,7 @@ impl Node {
         }
     }
 
-    pub fn element(&self) -> Option<&Element> {
+    pub fn element(&self) -> Option<&Element<'a>> {
         match self {
             Node::Element(e) => Some(e),
             _ => None,
@@ -30,10 +380,98 @@ impl Node {
             _ => None,
         }
     }
+
+    /// The text with mutable access, which detaches it from the parsed input
+    pub fn text_mut(&mut self) -> Option<&mut String> {
+        match self {
+            Node::Text(t) => Some(t.value.to_mut()),
+            _ => None,
+        }
+    }
+
+    pub fn element_mut(&mut self) -> Option<&mut Element<'a>> {
+        match self {
+            Node::Element(e) => Some(e),
+            _ => None,
+        }
+    }
+
+    pub fn doctype(&self) -> Option<&Doctype> {
+        match self {
+            Node::Doctype(d) => Some(d),
+            _ => None,
+        }
+    }
+
+    pub fn processing_instruction(&self) -> Option<&ProcessingInstruction<'a>> {
+        match self {
+            Node::ProcessingInstruction(p) => Some(p),
+            _ => None,
+        }
+    }
+
+    pub fn conditional_comment(&self) -> Option<&ConditionalComment<'a>> {
+        match self {
+            Node::ConditionalComment(c) => Some(c),
+            _ => None,
+        }
+    }
+
+    /// The children of an element or a conditional comment
+    pub fn children(&self) -> Option<&[Node<'a>]> {
+        match self {
+            Node::Element(e) => Some(&e.children),
+            Node::ConditionalComment(c) => Some(&c.children),
+            _ => None,
+        }
+    }
+
+    /// The children of an element or a conditional comment, with mutable access
+    pub fn children_mut(&mut self) -> Option<&mut Vec<Node<'a>>> {
+        match self {
+            Node::Element(e) => Some(&mut e.children),
+            Node::ConditionalComment(c) => Some(&mut c.children),
+            _ => None,
+        }
+    }
+
+    /// The comment with mutable access, which detaches it from the parsed input
+    pub fn comment_mut(&mut self) -> Option<&mut String> {
+        match self {
+            Node::Comment(t) => Some(t.value.to_mut()),
+            _ => None,
+        }
+    }
+
+    /// Detaches the node, and all of its children, from the parsed input, see
+    /// [`Dom::into_owned`](crate::Dom::into_owned)
+    pub fn into_owned(self) -> Node<'static> {
+        match self {
+            Node::Text(text) => Node::Text(text.into_owned()),
+            Node::Element(element) => Node::Element(element.into_owned()),
+            Node::Comment(comment) => Node::Comment(comment.into_owned()),
+            Node::Doctype(doctype) => Node::Doctype(doctype),
+            Node::ProcessingInstruction(instruction) => {
+                Node::ProcessingInstruction(instruction.into_owned())
+            }
+            Node::Cdata(cdata) => Node::Cdata(cdata.into_owned()),
+            Node::ConditionalComment(comment) => Node::ConditionalComment(comment.into_owned()),
+        }
+    }
+
+    /// Serializes the node, and all of its children, back to html.
+    pub fn to_html(&self) -> String {
+        self.to_string()
+    }
+
+    /// Serializes the node, and all of its children, to json, see [`JsonOptions`].
+    pub fn to_json_with(&self, options: &JsonOptions) -> crate::Result<String> {
+        json::to_string(&json::Json::new(self, options), options)
+    }
 }
 
-impl<'a> IntoIterator for &'a Node {
-    type Item = &'a Node;
+impl<'a> IntoIterator for &'a Node<'a> {
+    type Item = &'a Node<'a>;
     type IntoIter = NodeIntoIterator<'a>;
 
     fn into_iter(self) -> Self::IntoIter {
@@ -45,20 +483,17 @@ impl<'a> IntoIterator for &'a Node {
 }
 
 pub struct NodeIntoIterator<'a> {
-    node: &'a Node,
+    node: &'a Node<'a>,
     // We add/remove to this vec each time we go up/down a node three
-    index: Vec<(usize, &'a Node)>,
+    index: Vec<(usize, &'a Node<'a>)>,
 }
 
 impl<'a> Iterator for NodeIntoIterator<'a> {
-    type Item = &'a Node;
+    type Item = &'a Node<'a>;
 
     fn next(&mut self) -> Option<Self::Item> {
         // Get first child
-        let child = match self.node {
-            Node::Element(ref e) => e.children.get(0),
-            _ => None,
-        };
+        let child = self.node.children().and_then(<[Node]>::first);
 
         let result = match child {
             // If element has child, return child
@@ -68,7 +503,7 @@ impl<'a> Iterator for NodeIntoIterator<'a> {
                 Some(child)
             }
             // If element doesn't have a child, but is a child of another node
-            None if self.index.len() > 0 => {
+            None if !self.index.is_empty() => {
                 let mut has_finished = false;
                 let mut next_node = None;
 
@@ -76,11 +511,9 @@ impl<'a> Iterator for NodeIntoIterator<'a> {
                     // Try to get the next sibling of the parent node
                     if let Some((sibling_index, parent)) = self.index.pop() {
                         let next_sibling = sibling_index + 1;
-                        let sibling = if let Node::Element(ref e) = parent {
-                            e.children.get(next_sibling)
-                        } else {
-                            None
-                        };
+                        let sibling = parent
+                            .children()
+                            .and_then(|children| children.get(next_sibling));
                         if sibling.is_some() {
                             has_finished = true;
                             self.index.push((next_sibling, parent));
@@ -113,7 +546,7 @@ mod tests {
 
     #[test]
     fn node_utillity_functions() {
-        let node = Node::Text("test".to_string());
+        let node = Node::Text("test".into());
 
         assert_eq!(node.text(), Some("test"));
         assert_eq!(node.element(), None);
@@ -125,10 +558,10 @@ mod tests {
         assert_eq!(node.element(), Some(&Element::default()));
         assert_eq!(node.comment(), None);
 
-        let node = Node::Comment("test".to_string());
+        let node = Node::Comment("test".into());
 
         assert_eq!(node.text(), None);
         assert_eq!(node.element(), None);
         assert_eq!(node.comment(), Some("test"));
     }
-}
\ No newline at end of file
+}
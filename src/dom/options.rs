@@ -0,0 +1,320 @@
+use std::time::Duration;
+
+/// The default of [`ParseOptions::max_depth`], which also limits the depth of trees that are
+/// read from json
+pub(crate) const DEFAULT_MAX_DEPTH: usize = 512;
+
+/// Options that change how the html is parsed, used with [`Dom::parse_with`](super::Dom::parse_with).
+///
+/// ```rust
+/// use html_parser::{Dom, ParseOptions, TagCase};
+///
+/// let options = ParseOptions::new()
+///     .decode_entities(false)
+///     .keep_comments(false)
+///     .tag_case(TagCase::Lowercase);
+/// let dom = Dom::parse_with("<P>&amp;<!-- comment --></P>", &options).unwrap();
+/// let p = dom.children[0].element().unwrap();
+/// assert_eq!(p.name, "p");
+/// assert_eq!(p.children.len(), 1);
+/// assert_eq!(p.children[0].text(), Some("&amp;"));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseOptions {
+    pub(crate) decode_entities: bool,
+    pub(crate) source_spans: bool,
+    pub(crate) keep_whitespace_text: bool,
+    pub(crate) keep_comments: bool,
+    pub(crate) dangling_end_tags: DanglingEndTags,
+    pub(crate) tag_case: TagCase,
+    pub(crate) split_id_and_class: bool,
+    pub(crate) xhtml: bool,
+    pub(crate) cdata_sections: CdataSections,
+    pub(crate) tokenizer: Tokenizer,
+    pub(crate) max_depth: usize,
+    pub(crate) max_input_size: Option<usize>,
+    pub(crate) max_nodes: Option<usize>,
+    pub(crate) max_attributes: Option<usize>,
+    pub(crate) max_attribute_length: Option<usize>,
+    pub(crate) timeout: Option<Duration>,
+}
+
+/// What to do with an end tag that doesn't close an element, ex: `</span>` in
+/// `<div></span></div>`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DanglingEndTags {
+    /// Leave the end tag out of the tree
+    Discard,
+    /// Leave the end tag out of the tree, and add an error to [`Dom::errors`](super::Dom::errors)
+    Report,
+    /// Keep the end tag as a text node
+    KeepAsText,
+}
+
+/// How to keep a CDATA section, ex: `<![CDATA[ x < y ]]>`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CdataSections {
+    /// Keep the section as a [`Node::Cdata`](super::node::Node::Cdata)
+    Node,
+    /// Keep the content of the section as a text node, without decoding character references
+    Text,
+}
+
+/// How the input is split into tags, text and comments before the tree is built. Both give the
+/// same tree for the same input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tokenizer {
+    /// A hand-written tokenizer, which takes time linear in the size of the input
+    Streaming,
+    /// The pest grammar of earlier versions, which may take quadratic time on malformed input,
+    /// ex: many start tags without a `>`, and may report syntax errors at a later position.
+    /// Needs the `pest` feature
+    #[cfg(feature = "pest")]
+    Pest,
+}
+
+/// The case of element names
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TagCase {
+    /// Keep the names as they are written in the source
+    Preserve,
+    /// Convert the names to lowercase, as html element names are case-insensitive
+    Lowercase,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            decode_entities: true,
+            source_spans: true,
+            keep_whitespace_text: false,
+            keep_comments: true,
+            dangling_end_tags: DanglingEndTags::Discard,
+            tag_case: TagCase::Preserve,
+            split_id_and_class: true,
+            xhtml: false,
+            cdata_sections: CdataSections::Node,
+            tokenizer: Tokenizer::Streaming,
+            max_depth: DEFAULT_MAX_DEPTH,
+            max_input_size: None,
+            max_nodes: None,
+            max_attributes: None,
+            max_attribute_length: None,
+            timeout: None,
+        }
+    }
+}
+
+impl ParseOptions {
+    /// Creates the default options, which are the ones used by [`Dom::parse`](super::Dom::parse).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Decode character references, ex: `&amp;`, in text and attribute values. The content of
+    /// `script` and `style` is never decoded. Enabled by default, disable to keep the raw form.
+    pub fn decode_entities(mut self, decode_entities: bool) -> Self {
+        self.decode_entities = decode_entities;
+        self
+    }
+
+    /// Keep track of where elements and attributes are in the source, and keep the input in
+    /// [`Dom::source`](super::Dom::source) so that the text of spans can be looked up. Enabled by
+    /// default, disable to save memory if spans aren't needed.
+    pub fn source_spans(mut self, source_spans: bool) -> Self {
+        self.source_spans = source_spans;
+        self
+    }
+
+    /// Keep text nodes that only contain whitespace, ex: the indentation between elements.
+    /// Disabled by default. Whitespace around comments is never kept.
+    pub fn keep_whitespace_text(mut self, keep_whitespace_text: bool) -> Self {
+        self.keep_whitespace_text = keep_whitespace_text;
+        self
+    }
+
+    /// Keep comments in the tree. Enabled by default. Without them, conditional comments are left
+    /// out too, except for the content of downlevel-revealed ones, ex: the `div` in
+    /// `<!--[if !IE]><!--><div></div><!--<![endif]-->`, which is kept in their place.
+    pub fn keep_comments(mut self, keep_comments: bool) -> Self {
+        self.keep_comments = keep_comments;
+        self
+    }
+
+    /// What to do with end tags that don't close an element, see [`DanglingEndTags`]. They are
+    /// discarded by default.
+    pub fn dangling_end_tags(mut self, dangling_end_tags: DanglingEndTags) -> Self {
+        self.dangling_end_tags = dangling_end_tags;
+        self
+    }
+
+    /// The case of element names, see [`TagCase`]. Names are preserved by default.
+    pub fn tag_case(mut self, tag_case: TagCase) -> Self {
+        self.tag_case = tag_case;
+        self
+    }
+
+    /// Move the `id` and `class` attributes to [`Element::id`](super::element::Element::id) and
+    /// [`Element::classes`](super::element::Element::classes). Enabled by default, disable to keep
+    /// them in [`Element::attributes`](super::element::Element::attributes).
+    pub fn split_id_and_class(mut self, split_id_and_class: bool) -> Self {
+        self.split_id_and_class = split_id_and_class;
+        self
+    }
+
+    /// Parse the input as xhtml, where CDATA sections are recognized everywhere. In html they are
+    /// only recognized in svg and math, and are comments everywhere else. Disabled by default.
+    pub fn xhtml(mut self, xhtml: bool) -> Self {
+        self.xhtml = xhtml;
+        self
+    }
+
+    /// How to keep the CDATA sections that are recognized, see [`CdataSections`]. They are kept
+    /// as nodes by default.
+    pub fn cdata_sections(mut self, cdata_sections: CdataSections) -> Self {
+        self.cdata_sections = cdata_sections;
+        self
+    }
+
+    /// How the input is split into tokens, see [`Tokenizer`]. The hand-written tokenizer is used
+    /// by default.
+    pub fn tokenizer(mut self, tokenizer: Tokenizer) -> Self {
+        self.tokenizer = tokenizer;
+        self
+    }
+
+    /// The maximum number of elements, and conditional comments, that are nested in each other.
+    /// An element that would be nested deeper is opened next to the innermost open element
+    /// instead, as in browsers, with a [`ParseErrorKind::NestingTooDeep`](crate::ParseErrorKind)
+    /// warning. This keeps hostile input from overflowing the stack in code that walks the tree,
+    /// like dropping, comparing or serializing it. 512 by default, as in Chromium.
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// The maximum size of the input in bytes. Larger input isn't parsed at all, and fails with
+    /// [`Error::LimitExceeded`](crate::Error::LimitExceeded), except in
+    /// [`Dom::parse_lenient`](super::Dom::parse_lenient), where only the part of the input up to
+    /// the limit is parsed. Unlimited by default.
+    ///
+    /// This and the other limits are meant for untrusted input. Like every limit, it stops the
+    /// parsing with [`Error::LimitExceeded`](crate::Error::LimitExceeded), or, in lenient mode,
+    /// returns the tree of the input up to where the limit was exceeded, with a
+    /// [`ParseErrorKind::LimitExceeded`](crate::ParseErrorKind::LimitExceeded) error.
+    pub fn max_input_size(mut self, max_input_size: usize) -> Self {
+        self.max_input_size = Some(max_input_size);
+        self
+    }
+
+    /// The maximum number of nodes in the tree, including the copies of reopened formatting
+    /// elements and the nodes of conditional comments that aren't kept. In lenient mode the tree
+    /// keeps the nodes of the tag, or text, that exceeded the limit. Unlimited by default.
+    pub fn max_nodes(mut self, max_nodes: usize) -> Self {
+        self.max_nodes = Some(max_nodes);
+        self
+    }
+
+    /// The maximum number of attributes in a start tag, including repeated ones. Unlimited by
+    /// default.
+    pub fn max_attributes(mut self, max_attributes: usize) -> Self {
+        self.max_attributes = Some(max_attributes);
+        self
+    }
+
+    /// The maximum length of an attribute in the source in bytes, with its key, value and quotes.
+    /// Unlimited by default.
+    pub fn max_attribute_length(mut self, max_attribute_length: usize) -> Self {
+        self.max_attribute_length = Some(max_attribute_length);
+        self
+    }
+
+    /// The maximum time the parsing may take. The time is checked while the tree is built, so
+    /// with the pest [`Tokenizer`], which reads the whole input first, it may take longer.
+    /// Unlimited by default.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+}
+
+/// Options for [`Dom::to_json_with`](super::Dom::to_json_with), [`Node::to_json_with`](super::node::Node::to_json_with)
+/// and [`Element::to_json_with`](super::element::Element::to_json_with).
+///
+/// ```rust
+/// use html_parser::{Dom, JsonOptions, JsonSchema};
+///
+/// let dom = Dom::parse("<p>text<!--comment--></p>").unwrap();
+/// let options = JsonOptions::new().schema(JsonSchema::Tagged);
+/// assert_eq!(
+///     dom.to_json_with(&options).unwrap(),
+///     r#"{"treeType":"documentFragment","children":[{"type":"element","name":"p","variant":"normal","children":[{"type":"text","value":"text"},{"type":"comment","value":"comment"}]}]}"#
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonOptions {
+    pub(crate) schema: JsonSchema,
+    pub(crate) source_spans: bool,
+    pub(crate) errors: bool,
+    pub(crate) pretty: bool,
+}
+
+/// The shape of the json output.
+///
+/// In both schemas the dom is an object with `treeType` and `children`, and elements are objects
+/// with `name`, `variant` and, if they aren't empty, `id`, `attributes`, `classes` and `children`.
+/// Other nodes, like doctypes, are objects with a `type`, ex: `{"type": "doctype", "name": "html"}`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JsonSchema {
+    /// The schema of [`Dom::to_json`](super::Dom::to_json). Text and comments are plain strings,
+    /// so they can't be told apart, and attributes are an object where only the first of
+    /// repeated keys is kept.
+    Untagged,
+    /// Every node is an object with a `type` of `"element"`, `"text"` or `"comment"`. Text and
+    /// comments have their content in `value`, and attributes are a list of objects with `key`
+    /// and `value`, in source order and including repeated keys.
+    Tagged,
+}
+
+impl Default for JsonOptions {
+    fn default() -> Self {
+        Self {
+            schema: JsonSchema::Untagged,
+            source_spans: false,
+            errors: false,
+            pretty: false,
+        }
+    }
+}
+
+impl JsonOptions {
+    /// Creates the default options, which are the ones used by [`Dom::to_json`](super::Dom::to_json).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The shape of the output, see [`JsonSchema`]. Untagged by default.
+    pub fn schema(mut self, schema: JsonSchema) -> Self {
+        self.schema = schema;
+        self
+    }
+
+    /// Add a `sourceSpan` to elements, and to text, comments and attributes with the tagged
+    /// schema. Disabled by default.
+    pub fn source_spans(mut self, source_spans: bool) -> Self {
+        self.source_spans = source_spans;
+        self
+    }
+
+    /// Add the [`Dom::errors`](super::Dom::errors) as `errors` to the dom. Disabled by default.
+    pub fn errors(mut self, errors: bool) -> Self {
+        self.errors = errors;
+        self
+    }
+
+    /// Indent the output. Disabled by default.
+    pub fn pretty(mut self, pretty: bool) -> Self {
+        self.pretty = pretty;
+        self
+    }
+}
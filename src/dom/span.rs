@@ -1,10 +1,16 @@
-use serde::{Serialize};
+use serde::{Deserialize, Serialize};
 
 /// Span of the information in the parsed source.
-#[derive(Debug, Default, Clone, Serialize, PartialEq)]
+///
+/// The span only holds positions, use [`Dom::span_text`](super::Dom::span_text) or
+/// [`SourceSpan::text`] to get the text of the span.
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct SourceSpan {
-    pub text: String,
+    /// Byte offset of the start of the span
+    pub start: usize,
+    /// Byte offset of the end of the span, exclusive
+    pub end: usize,
     pub start_line: usize,
     pub end_line: usize,
     pub start_column: usize,
@@ -13,18 +19,108 @@ pub struct SourceSpan {
 
 impl SourceSpan {
     pub fn new(
-        text: String,
+        start: usize,
+        end: usize,
         start_line: usize,
         end_line: usize,
         start_column: usize,
         end_column: usize,
     ) -> Self {
         Self {
-            text,
+            start,
+            end,
             start_line,
             end_line,
             start_column,
             end_column,
         }
     }
+
+    /// The text of the span in `source`, which has to be the parsed input. An empty string is
+    /// returned if the span is out of bounds.
+    pub fn text<'a>(&self, source: &'a str) -> &'a str {
+        source.get(self.start..self.end).unwrap_or_default()
+    }
+}
+
+/// Converts byte offsets to 1-based lines and columns, where columns are counted in characters.
+///
+/// Finding the line is a binary search, and counting the characters of the column is bounded by
+/// the block size, so that long (minified) lines don't make lookups quadratic.
+pub(crate) struct LineIndex<'a> {
+    input: &'a str,
+    line_starts: Vec<usize>,
+    /// The number of characters before the start of each block
+    block_chars: Vec<usize>,
+}
+
+const BLOCK_SIZE: usize = 64;
+
+impl<'a> LineIndex<'a> {
+    pub fn new(input: &'a str) -> Self {
+        let bytes = input.as_bytes();
+        let mut line_starts = vec![0];
+        line_starts.extend(
+            bytes
+                .iter()
+                .enumerate()
+                .filter(|(_, b)| **b == b'\n')
+                .map(|(index, _)| index + 1),
+        );
+
+        let mut block_chars = Vec::with_capacity(bytes.len() / BLOCK_SIZE + 1);
+        let mut chars = 0;
+        for block in bytes.chunks(BLOCK_SIZE) {
+            block_chars.push(chars);
+            chars += count_chars(block);
+        }
+        block_chars.push(chars);
+
+        Self {
+            input,
+            line_starts,
+            block_chars,
+        }
+    }
+
+    pub fn line_col(&self, offset: usize) -> (usize, usize) {
+        let line = match self.line_starts.binary_search(&offset) {
+            Ok(line) => line,
+            Err(line) => line - 1,
+        };
+        let column = self.chars_before(offset) - self.chars_before(self.line_starts[line]);
+        (line + 1, column + 1)
+    }
+
+    pub fn span(&self, start: usize, end: usize) -> SourceSpan {
+        let (start_line, start_column) = self.line_col(start);
+        let (end_line, end_column) = self.line_col(end);
+        SourceSpan::new(start, end, start_line, end_line, start_column, end_column)
+    }
+
+    fn chars_before(&self, offset: usize) -> usize {
+        let block = offset / BLOCK_SIZE;
+        let block_start = block * BLOCK_SIZE;
+        self.block_chars[block] + count_chars(&self.input.as_bytes()[block_start..offset])
+    }
+}
+
+/// Counts the characters in utf-8 bytes, by counting all bytes that aren't continuation bytes
+fn count_chars(bytes: &[u8]) -> usize {
+    bytes.iter().filter(|b| (**b & 0xC0) != 0x80).count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_index_matches_pest_positions() {
+        let input = "a\r\nbå\n\nc😀d".repeat(40);
+        let index = LineIndex::new(&input);
+        for offset in (0..=input.len()).filter(|x| input.is_char_boundary(*x)) {
+            let expected = pest::Position::new(&input, offset).unwrap().line_col();
+            assert_eq!(index.line_col(offset), expected, "offset {}", offset);
+        }
+    }
 }
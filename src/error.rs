@@ -1,9 +1,17 @@
+use crate::dom::span::SourceSpan;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::Duration;
 use thiserror::Error as ThisError;
 
 #[derive(ThisError, Debug)]
 pub enum Error {
     #[error("{0}")]
-    Parsing(String),
+    Parsing(ParseError),
+    #[error("{0}")]
+    LimitExceeded(Limit),
+    #[error("{0}")]
+    Selector(String),
     #[error("{0}")]
     Cli(String),
     #[error("{0}")]
@@ -13,3 +21,236 @@ pub enum Error {
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
+
+impl From<ParseError> for Error {
+    fn from(error: ParseError) -> Self {
+        Error::Parsing(error)
+    }
+}
+
+/// The kind of a [`ParseError`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[non_exhaustive]
+pub enum ParseErrorKind {
+    /// The input doesn't match the grammar, see [`ParseError::expected`]
+    Syntax,
+    /// A part of the input was recognized, but it can't be turned into a node
+    UnsupportedNode,
+    /// An attribute couldn't be built
+    InvalidAttribute,
+    /// An end tag without a matching start tag, see
+    /// [`ParseOptions::dangling_end_tags`](crate::ParseOptions::dangling_end_tags). Discarded end
+    /// tags are still reported by [`Dom::parse_strict`](crate::Dom::parse_strict)
+    DanglingEndTag,
+    /// An element, or a conditional comment, that is closed without an end tag that can't be left
+    /// out, ex: the `div` in `<div><p>text</p>`
+    UnclosedElement,
+    /// A formatting element, like `b`, that is closed or opened inside another element that was
+    /// opened after it, ex: the `</b>` in `<b><i>x</b>y</i>`
+    MisnestedElement,
+    /// An attribute that is repeated in the same start tag, ex: `<div id="a" id="b">`, where only
+    /// the first is used. Only reported by [`Dom::parse_strict`](crate::Dom::parse_strict)
+    DuplicateAttribute,
+    /// A self-closing start tag of an html element that isn't void, ex: `<div/>`. Browsers ignore
+    /// the `/`, but here the element is closed. Only reported by
+    /// [`Dom::parse_strict`](crate::Dom::parse_strict)
+    SelfClosingNonVoidElement,
+    /// An element, or a conditional comment, that would be nested deeper than
+    /// [`ParseOptions::max_depth`](crate::ParseOptions::max_depth), and is opened next to the
+    /// innermost open element instead. Only the first one is reported
+    NestingTooDeep,
+    /// A limit of the [`ParseOptions`](crate::ParseOptions) was exceeded, and the rest of the input
+    /// was left out of the tree. Only in [`Dom::errors`](crate::Dom::errors) of
+    /// [`Dom::parse_lenient`](crate::Dom::parse_lenient), the other modes return
+    /// [`Error::LimitExceeded`]
+    LimitExceeded,
+    /// A document with more than one `html` element
+    MultipleHtmlElements,
+    /// A document fragment with an element that is only allowed in a document, like `body`
+    InvalidFragment,
+}
+
+/// A limit of the [`ParseOptions`](crate::ParseOptions) that stops the parsing of untrusted input,
+/// with the value it was set to. See [`Error::LimitExceeded`].
+///
+/// ```rust
+/// use html_parser::{Dom, Error, Limit, ParseOptions};
+///
+/// let options = ParseOptions::new().max_attributes(2);
+/// match Dom::parse_with("<p a b c>text</p>", &options) {
+///     Err(Error::LimitExceeded(limit)) => assert_eq!(limit, Limit::Attributes(2)),
+///     _ => unreachable!(),
+/// }
+/// ```
+#[derive(ThisError, Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Limit {
+    /// See [`ParseOptions::max_input_size`](crate::ParseOptions::max_input_size)
+    #[error("Input too large: more than {0} bytes")]
+    InputSize(usize),
+    /// See [`ParseOptions::max_nodes`](crate::ParseOptions::max_nodes)
+    #[error("Too many nodes: more than {0}")]
+    Nodes(usize),
+    /// See [`ParseOptions::max_attributes`](crate::ParseOptions::max_attributes)
+    #[error("Too many attributes: more than {0} in a start tag")]
+    Attributes(usize),
+    /// See [`ParseOptions::max_attribute_length`](crate::ParseOptions::max_attribute_length)
+    #[error("Attribute too long: more than {0} bytes")]
+    AttributeLength(usize),
+    /// See [`ParseOptions::timeout`](crate::ParseOptions::timeout)
+    #[error("Parsing took too long: more than {0:?}")]
+    Timeout(Duration),
+}
+
+/// How serious a [`ParseError`] is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Severity {
+    /// Parsing failed, or the part of the input was left out of the tree
+    Error,
+    /// The input isn't valid html, but the tree still represents it
+    Warning,
+}
+
+/// An error, or a warning, that was found while parsing.
+///
+/// Errors that stop the parsing are returned as [`Error::Parsing`], the others are collected in
+/// [`Dom::errors`](crate::Dom::errors).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    pub severity: Severity,
+    pub message: String,
+    /// Where in the input the error was found
+    pub span: SourceSpan,
+    /// What the parser expected to find instead, for syntax errors
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub expected: Vec<String>,
+    /// The syntax error as `Display` has always printed it, with the line of the input
+    #[serde(skip)]
+    pub(crate) snippet: Option<Box<str>>,
+}
+
+impl ParseError {
+    pub fn new(kind: ParseErrorKind, message: impl Into<String>, span: SourceSpan) -> Self {
+        Self {
+            kind,
+            severity: Severity::Error,
+            message: message.into(),
+            span,
+            expected: vec![],
+            snippet: None,
+        }
+    }
+
+    /// Keeps the line of `source` that the error points at for `Display`, in the format of the
+    /// errors of pest, ex:
+    ///
+    /// ```text
+    ///  --> 1:6
+    ///   |
+    /// 1 | <div <
+    ///   |      ^---
+    ///   |
+    ///   = expected element name
+    /// ```
+    pub(crate) fn with_snippet(mut self, source: &str) -> Self {
+        let line_number = self.span.start_line.max(1);
+        let line = source.lines().nth(line_number - 1).unwrap_or_default();
+        let gutter = " ".repeat(line_number.to_string().len());
+        let indent = line
+            .chars()
+            .take(self.span.start_column.max(1) - 1)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect::<String>();
+        self.snippet = Some(
+            format!(
+                "{}--> {}:{}\n{} |\n{} | {}\n{} | {}^---\n{} |\n{} = {}",
+                gutter,
+                line_number,
+                self.span.start_column,
+                gutter,
+                line_number,
+                line,
+                gutter,
+                indent,
+                gutter,
+                gutter,
+                self.message
+            )
+            .into(),
+        );
+        self
+    }
+
+    /// Renders the error with the line of `source` that it points at, where `source` has to be
+    /// the parsed input.
+    ///
+    /// ```text
+    /// error: expected element name
+    ///  --> 1:6
+    ///   |
+    /// 1 | <div <
+    ///   |      ^
+    /// ```
+    pub fn render(&self, source: &str) -> String {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        let line_number = self.span.start_line.max(1);
+        let column = self.span.start_column.max(1);
+        let line = source.lines().nth(line_number - 1).unwrap_or_default();
+
+        // Underline the span, up to the end of the line for spans over multiple lines
+        let line_length = line.chars().count();
+        let end_column = if self.span.end_line == self.span.start_line {
+            self.span.end_column
+        } else {
+            line_length + 1
+        };
+        let carets = end_column.saturating_sub(column).max(1);
+
+        let gutter = " ".repeat(line_number.to_string().len());
+        let indent = line
+            .chars()
+            .take(column - 1)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect::<String>();
+
+        format!(
+            "{}: {}\n{}--> {}:{}\n{} |\n{} | {}\n{} | {}{}",
+            severity,
+            self.message,
+            gutter,
+            line_number,
+            column,
+            gutter,
+            line_number,
+            line,
+            gutter,
+            indent,
+            "^".repeat(carets)
+        )
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(snippet) = &self.snippet {
+            return f.write_str(snippet);
+        }
+        f.write_str(&self.message)?;
+        // Syntax errors are printed with their position, also without the input to show
+        if self.kind == ParseErrorKind::Syntax {
+            write!(
+                f,
+                " at line {}, column {}",
+                self.span.start_line, self.span.start_column
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -1,5 +1,151 @@
+use pest::iterators::Pair;
+use pest::Parser;
 use pest_derive::Parser;
 
+use crate::dom::formatting;
+use crate::error::ParseError;
+use crate::tokenizer::{Span, Tag, TagAttribute, Token};
+
 #[derive(Parser)]
 #[grammar = "grammar/rules.pest"]
 pub struct Grammar;
+
+/// The tokens of the input, read with the grammar. The lenient grammar matches any input.
+pub(crate) fn tokens(
+    input: &str,
+    lenient: bool,
+) -> Result<impl Iterator<Item = Result<Token<'_>, ParseError>>, ParseError> {
+    let rule = match lenient {
+        true => Rule::html_lenient,
+        false => Rule::html,
+    };
+    let pairs = Grammar::parse(rule, input).map_err(formatting::syntax_error)?;
+    Ok(pairs
+        .filter(|pair| pair.as_rule() != Rule::EOI)
+        .map(move |pair| Ok(token(input, pair))))
+}
+
+fn to_span<'a>(input: &'a str, pair: &Pair<'a, Rule>) -> Span<'a> {
+    let span = pair.as_span();
+    Span::new(input, span.start(), span.end())
+}
+
+fn token<'a>(input: &'a str, pair: Pair<'a, Rule>) -> Token<'a> {
+    let span = to_span(input, &pair);
+    match pair.as_rule() {
+        Rule::doctype => Token::Doctype {
+            span,
+            content: pair.into_inner().as_str(),
+        },
+        Rule::node_text => Token::Text(span),
+        Rule::node_comment => Token::Comment {
+            span,
+            value: pair.into_inner().as_str(),
+        },
+        Rule::node_bogus_comment => {
+            let inner = pair.into_inner().next().expect("bogus comment content");
+            let unclosed = inner.as_rule() == Rule::comment_unclosed;
+            let value = match unclosed {
+                true => inner.into_inner().as_str(),
+                false => inner.as_str(),
+            };
+            Token::BogusComment {
+                span,
+                value,
+                unclosed,
+            }
+        }
+        Rule::node_cdata => Token::Cdata {
+            span,
+            content: pair.into_inner().as_str(),
+        },
+        Rule::node_process_instruct => {
+            let mut target = "";
+            let mut data = "";
+            for pair in pair.into_inner() {
+                match pair.as_rule() {
+                    Rule::pi_target => target = pair.as_str(),
+                    Rule::pi_data => data = pair.as_str(),
+                    _ => unreachable!(
+                        "[processing instruction] unknown rule: {:?}",
+                        pair.as_rule()
+                    ),
+                }
+            }
+            Token::ProcessingInstruction { span, target, data }
+        }
+        Rule::el_start => Token::StartTag(tag(input, pair)),
+        Rule::el_end => Token::EndTag {
+            span,
+            name: pair.into_inner().as_str(),
+        },
+        Rule::el_raw_text => {
+            let mut inner = pair.into_inner();
+            let start_tag = tag(input, inner.next().expect("start tag"));
+            let content = to_span(input, &inner.next().expect("raw text content"));
+            let end_tag = inner.next().map(|pair| to_span(input, &pair));
+            Token::RawText {
+                start_tag,
+                content,
+                end_tag,
+            }
+        }
+        Rule::conditional_comment_start => {
+            let mut condition = "";
+            let mut revealed = false;
+            for pair in pair.into_inner() {
+                match pair.as_rule() {
+                    Rule::comment_if_condition => condition = pair.as_str(),
+                    Rule::comment_if_revealed => revealed = true,
+                    _ => unreachable!("[conditional comment] unknown rule: {:?}", pair.as_rule()),
+                }
+            }
+            Token::ConditionalCommentStart {
+                span,
+                condition,
+                revealed,
+            }
+        }
+        Rule::conditional_comment_end => Token::ConditionalCommentEnd(span),
+        _ => unreachable!("[token] unknown rule: {:?}", pair.as_rule()),
+    }
+}
+
+/// A start tag, or the start tag of a raw text element
+fn tag<'a>(input: &'a str, pair: Pair<'a, Rule>) -> Tag<'a> {
+    let mut tag = Tag {
+        span: to_span(input, &pair),
+        name: "",
+        attributes: vec![],
+        self_closing: false,
+    };
+    for pair in pair.into_inner() {
+        match pair.as_rule() {
+            Rule::el_name | Rule::el_raw_text_name => tag.name = pair.as_str(),
+            Rule::attr => tag.attributes.push(attribute(input, pair)),
+            Rule::el_self_closing => tag.self_closing = true,
+            _ => unreachable!("[start tag] unknown rule: {:?}", pair.as_rule()),
+        }
+    }
+    tag
+}
+
+fn attribute<'a>(input: &'a str, pair: Pair<'a, Rule>) -> TagAttribute<'a> {
+    let mut attribute = TagAttribute {
+        span: to_span(input, &pair),
+        key: to_span(input, &pair),
+        value: None,
+    };
+    for pair in pair.into_inner() {
+        match pair.as_rule() {
+            Rule::attr_key => attribute.key = to_span(input, &pair),
+            Rule::attr_non_quoted => attribute.value = Some(to_span(input, &pair)),
+            Rule::attr_quoted => {
+                let value = pair.into_inner().next().expect("attribute value");
+                attribute.value = Some(to_span(input, &value));
+            }
+            _ => unreachable!("[attribute] unknown rule: {:?}", pair.as_rule()),
+        }
+    }
+    attribute
+}
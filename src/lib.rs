@@ -4,10 +4,10 @@
 //!
 //! # Html parser
 //!
-//! A simple and general purpose html/xhtml parser lib/bin, using [Pest](https://pest.rs/).
+//! A simple and general purpose html/xhtml parser lib/bin, with a hand-written tokenizer or a [Pest](https://pest.rs/) grammar.
 //!
 //! ## Features
-//! - Parse html & xhtml (not xml processing instructions)
+//! - Parse html & xhtml, including xml processing instructions and CDATA sections
 //! - Parse html-documents
 //! - Parse html-fragments
 //! - Parse empty documents
@@ -16,12 +16,31 @@
 //! - Removes comments
 //! - Removes dangling elements
 //! - Iterate over all nodes in the dom tree
+//! - Serialize the dom back to html
+//! - Select elements with css selectors
+//! - Modify the dom; insert, wrap, unwrap and remove nodes
+//! - Navigate to parents and siblings through an arena representation of the dom
+//! - Decode character references, ex: `&amp;`, in text and attribute values
+//! - Parse errors with their kind, line and column, rendered with a snippet of the input
+//! - Configure the shape of the tree with `ParseOptions`; whitespace, comments, dangling end tags, tag case and more
+//! - Serialize to json with a tagged schema, that tells text and comments apart, and optional source spans and errors
+//! - Read a dom back from json
+//! - Keep the doctype, with its name and identifiers, and derive the quirks mode from it
+//! - Keep conditional comments, ex: `<!--[if mso]>`, with their condition and parsed content
+//! - Close elements with an implied end tag, ex: `<li>a<li>b`, and unclosed elements at the end of the input, as browsers do
+//! - Recover from misnested formatting elements, ex: `<b><i>x</b>y</i>`, with the adoption agency algorithm of browsers
+//! - Parse any input with `Dom::parse_lenient`, which keeps bogus markup as comments and collects the errors
+//! - Validate the input with `Dom::parse_strict`, which stops at the first error or warning with its position
+//! - Borrow text, names and attributes from the input where nothing had to be decoded, with `Dom::into_owned` to detach the tree
+//! - Tokenize the input in linear time with a hand-written tokenizer, or with the pest grammar behind the `pest` feature
+//! - Limit the nesting depth with `ParseOptions::max_depth`, so that hostile input can't overflow the stack
+//! - Limit the size of the input, the number of nodes and attributes, the length of attributes and the parsing time of untrusted input
+//! - Read the input as a stream of events, ex: start tags and text, with `Events`, without building the tree
 //!
 //! ## What is it not
 //!
 //! - It's not a high-performance browser-grade parser
 //! - It's not suitable for html validation
-//! - It's not a parser that includes element selection or dom manipulation
 //!
 //! If your requirements matches any of the above, then you're most likely looking for one of the crates below:
 //!
@@ -98,13 +117,23 @@
 
 mod dom;
 mod error;
+#[cfg(feature = "pest")]
 mod grammar;
+mod selector;
+mod tokenizer;
 
-use grammar::Rule;
-
-pub use crate::dom::element::{Element, ElementVariant};
-pub use crate::dom::node::Node;
+pub use crate::dom::arena::{ArenaDom, NodeId};
+pub use crate::dom::attribute::{Attribute, Attributes};
+pub use crate::dom::doctype::{Doctype, QuirksMode};
+pub use crate::dom::element::{Element, ElementVariant, SplitPositions};
+pub use crate::dom::events::{Event, Events};
+pub use crate::dom::node::{Cdata, Comment, ConditionalComment, Node, ProcessingInstruction, Text};
+pub use crate::dom::options::{
+    CdataSections, DanglingEndTags, JsonOptions, JsonSchema, ParseOptions, TagCase, Tokenizer,
+};
+pub use crate::dom::span::SourceSpan;
 pub use crate::dom::Dom;
 pub use crate::dom::DomVariant;
-pub use crate::error::Error;
 pub use crate::error::Result;
+pub use crate::error::{Error, Limit, ParseError, ParseErrorKind, Severity};
+pub use crate::selector::Selector;
@@ -0,0 +1,333 @@
+use super::{
+    AttributeOperator, AttributeSelector, Combinator, ComplexSelector, Component, CompoundSelector,
+    PseudoClass, Selector,
+};
+use crate::dom::element::Element;
+use crate::dom::node::Node;
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+/// An element together with its position among its siblings.
+#[derive(Clone, Copy)]
+struct Frame<'a> {
+    element: &'a Element<'a>,
+    /// `None` for the scope of [`Selector::select_in`](super::Selector::select_in), whose
+    /// siblings aren't known
+    siblings: Option<&'a [Node<'a>]>,
+    index: usize,
+}
+
+/// The 1-based position of an element among its element siblings, and among those with the same
+/// name, with the number of each
+#[derive(Clone, Copy, Default)]
+struct Position {
+    index: usize,
+    count: usize,
+    index_of_type: usize,
+    count_of_type: usize,
+}
+
+/// The positions of the elements in the lists of siblings that were looked at, which are counted
+/// once per list, so that positional pseudo-classes don't count the siblings of every element.
+#[derive(Default)]
+struct Positions {
+    /// By the address of the first node of the siblings, and then by the index of the node
+    siblings: RefCell<HashMap<usize, Vec<Position>>>,
+}
+
+impl Positions {
+    fn get(&self, siblings: &[Node], index: usize) -> Position {
+        let key = siblings.as_ptr() as usize;
+        let mut cache = self.siblings.borrow_mut();
+        let positions = cache.entry(key).or_insert_with(|| Self::count(siblings));
+        positions[index]
+    }
+
+    fn count(siblings: &[Node]) -> Vec<Position> {
+        let mut positions = vec![Position::default(); siblings.len()];
+        let mut count = 0;
+        let mut count_of_type = HashMap::new();
+        for (node, position) in siblings.iter().zip(&mut positions) {
+            if let Some(element) = node.element() {
+                count += 1;
+                let of_type = count_of_type
+                    .entry(element.name.to_ascii_lowercase())
+                    .or_insert(0);
+                *of_type += 1;
+                position.index = count;
+                position.index_of_type = *of_type;
+            }
+        }
+        for (node, position) in siblings.iter().zip(&mut positions) {
+            if let Some(element) = node.element() {
+                position.count = count;
+                position.count_of_type = count_of_type[&element.name.to_ascii_lowercase()];
+            }
+        }
+        positions
+    }
+}
+
+/// An element in the tree, with access to its ancestors and siblings.
+#[derive(Clone, Copy)]
+struct Cursor<'p, 'a> {
+    ancestors: &'p [Frame<'a>],
+    frame: Frame<'a>,
+    positions: &'p Positions,
+}
+
+impl<'p, 'a> Cursor<'p, 'a> {
+    fn parent(&self) -> Option<Cursor<'p, 'a>> {
+        let (frame, ancestors) = self.ancestors.split_last()?;
+        Some(Cursor {
+            ancestors,
+            frame: *frame,
+            positions: self.positions,
+        })
+    }
+
+    fn sibling_at(&self, index: usize) -> Option<Cursor<'p, 'a>> {
+        match self.frame.siblings?.get(index) {
+            Some(Node::Element(element)) => Some(Cursor {
+                ancestors: self.ancestors,
+                frame: Frame {
+                    element,
+                    siblings: self.frame.siblings,
+                    index,
+                },
+                positions: self.positions,
+            }),
+            _ => None,
+        }
+    }
+
+    fn previous_siblings(self) -> impl Iterator<Item = Cursor<'p, 'a>> {
+        (0..self.frame.index)
+            .rev()
+            .filter_map(move |index| self.sibling_at(index))
+    }
+
+    /// Whether the 1-based position among the element siblings, and the number of element
+    /// siblings, match, optionally only counting elements with the same name. This is never the
+    /// case if the siblings aren't known.
+    fn position_matches(&self, same_type: bool, matches: impl Fn(usize, usize) -> bool) -> bool {
+        let siblings = match self.frame.siblings {
+            Some(siblings) => siblings,
+            None => return false,
+        };
+        let position = self.positions.get(siblings, self.frame.index);
+        if same_type {
+            matches(position.index_of_type, position.count_of_type)
+        } else {
+            matches(position.index, position.count)
+        }
+    }
+}
+
+pub fn select<'a>(
+    selector: &Selector,
+    scope: Option<&'a Element<'a>>,
+    nodes: &'a [Node<'a>],
+) -> Vec<&'a Element<'a>> {
+    let mut result = vec![];
+    let mut path = vec![];
+    let positions = Positions::default();
+    if let Some(element) = scope {
+        path.push(Frame {
+            element,
+            siblings: None,
+            index: 0,
+        });
+    }
+
+    // The tree is walked without recursion so that deep trees can't overflow the stack.
+    // The levels of elements belong to the last element in `path`, while the children of
+    // conditional comments are on a level of their own within the element around the comment.
+    let mut levels = vec![(nodes, 0, false)];
+    while let Some(level) = levels.last_mut() {
+        let (siblings, index, in_element) = *level;
+        if index >= siblings.len() {
+            levels.pop();
+            if in_element {
+                path.pop();
+            }
+            continue;
+        }
+        level.1 += 1;
+
+        match &siblings[index] {
+            Node::Element(element) => {
+                let frame = Frame {
+                    element,
+                    siblings: Some(siblings),
+                    index,
+                };
+                let cursor = Cursor {
+                    ancestors: &path,
+                    frame,
+                    positions: &positions,
+                };
+                if matches_selector(selector, cursor) {
+                    result.push(element);
+                }
+                path.push(frame);
+                levels.push((&element.children, 0, true));
+            }
+            Node::ConditionalComment(comment) => levels.push((&comment.children, 0, false)),
+            _ => (),
+        }
+    }
+    result
+}
+
+fn matches_selector(selector: &Selector, cursor: Cursor) -> bool {
+    selector
+        .selectors
+        .iter()
+        .any(|complex| matches_complex(complex, complex.compounds.len() - 1, cursor))
+}
+
+/// Matches the compounds up to, and including, `index` from right to left
+fn matches_complex(complex: &ComplexSelector, index: usize, cursor: Cursor) -> bool {
+    if !matches_compound(&complex.compounds[index], cursor) {
+        return false;
+    }
+    if index == 0 {
+        return true;
+    }
+
+    let next = index - 1;
+    match complex.combinators[next] {
+        Combinator::Child => cursor
+            .parent()
+            .is_some_and(|parent| matches_complex(complex, next, parent)),
+        Combinator::Descendant => {
+            let mut ancestor = cursor.parent();
+            while let Some(current) = ancestor {
+                if matches_complex(complex, next, current) {
+                    return true;
+                }
+                ancestor = current.parent();
+            }
+            false
+        }
+        Combinator::NextSibling => cursor
+            .previous_siblings()
+            .next()
+            .is_some_and(|sibling| matches_complex(complex, next, sibling)),
+        Combinator::SubsequentSibling => cursor
+            .previous_siblings()
+            .any(|sibling| matches_complex(complex, next, sibling)),
+    }
+}
+
+fn matches_compound(compound: &CompoundSelector, cursor: Cursor) -> bool {
+    let element = cursor.frame.element;
+    if let Some(name) = &compound.name {
+        if !element.name.eq_ignore_ascii_case(name) {
+            return false;
+        }
+    }
+    compound.components.iter().all(|component| match component {
+        Component::Id(id) => match &element.id {
+            Some(x) => x == id,
+            None => matches!(attribute_value(element, "id"), Some(Some(x)) if x == id.as_str()),
+        },
+        Component::Class(class) if element.classes.is_empty() => {
+            matches!(attribute_value(element, "class"), Some(Some(x)) if x.split_whitespace().any(|x| x == class))
+        }
+        Component::Class(class) => element.classes.iter().any(|x| x == class),
+        Component::Attribute(attribute) => matches_attribute(attribute, element),
+        Component::Pseudo(pseudo_class) => matches_pseudo_class(pseudo_class, cursor),
+    })
+}
+
+/// Looks up an attribute by name, including the id and class that are stored separately. They
+/// are only in the attributes if they weren't split out when parsing.
+fn attribute_value<'a>(element: &'a Element<'a>, name: &str) -> Option<Option<Cow<'a, str>>> {
+    if name.eq_ignore_ascii_case("id") {
+        if let Some(id) = &element.id {
+            return Some(Some(Cow::Borrowed(id)));
+        }
+    }
+    if name.eq_ignore_ascii_case("class") && !element.classes.is_empty() {
+        return Some(Some(Cow::Owned(element.classes.join(" "))));
+    }
+    element
+        .attributes
+        .iter()
+        .find(|attribute| attribute.key.eq_ignore_ascii_case(name))
+        .map(|attribute| attribute.value.as_deref().map(Cow::Borrowed))
+}
+
+fn matches_attribute(selector: &AttributeSelector, element: &Element) -> bool {
+    let value = match attribute_value(element, &selector.name) {
+        Some(value) => value,
+        None => return false,
+    };
+    let (operator, expected) = match &selector.operation {
+        Some(operation) => operation,
+        None => return true,
+    };
+
+    let value = value.unwrap_or_default();
+    let (value, expected) = if selector.case_insensitive {
+        (
+            Cow::Owned(value.to_lowercase()),
+            Cow::Owned(expected.to_lowercase()),
+        )
+    } else {
+        (value, Cow::Borrowed(expected.as_str()))
+    };
+    let (value, expected) = (value.as_ref(), expected.as_ref());
+
+    match operator {
+        AttributeOperator::Equals => value == expected,
+        AttributeOperator::Includes => {
+            !expected.is_empty() && value.split_whitespace().any(|x| x == expected)
+        }
+        AttributeOperator::DashMatch => {
+            value == expected
+                || (value.starts_with(expected) && value[expected.len()..].starts_with('-'))
+        }
+        AttributeOperator::Prefix => !expected.is_empty() && value.starts_with(expected),
+        AttributeOperator::Suffix => !expected.is_empty() && value.ends_with(expected),
+        AttributeOperator::Substring => !expected.is_empty() && value.contains(expected),
+    }
+}
+
+fn matches_pseudo_class(pseudo_class: &PseudoClass, cursor: Cursor) -> bool {
+    match pseudo_class {
+        PseudoClass::Root => cursor.ancestors.is_empty() && cursor.frame.siblings.is_some(),
+        PseudoClass::Empty => cursor
+            .frame
+            .element
+            .children
+            .iter()
+            .all(|node| matches!(node, Node::Comment(_) | Node::ConditionalComment(_))),
+        PseudoClass::FirstChild => cursor.position_matches(false, |position, _| position == 1),
+        PseudoClass::LastChild => {
+            cursor.position_matches(false, |position, total| position == total)
+        }
+        PseudoClass::OnlyChild => cursor.position_matches(false, |_, total| total == 1),
+        PseudoClass::FirstOfType => cursor.position_matches(true, |position, _| position == 1),
+        PseudoClass::LastOfType => {
+            cursor.position_matches(true, |position, total| position == total)
+        }
+        PseudoClass::OnlyOfType => cursor.position_matches(true, |_, total| total == 1),
+        PseudoClass::NthChild(nth) => {
+            cursor.position_matches(false, |position, _| nth.matches(position))
+        }
+        PseudoClass::NthLastChild(nth) => {
+            cursor.position_matches(false, |position, total| nth.matches(total - position + 1))
+        }
+        PseudoClass::NthOfType(nth) => {
+            cursor.position_matches(true, |position, _| nth.matches(position))
+        }
+        PseudoClass::NthLastOfType(nth) => {
+            cursor.position_matches(true, |position, total| nth.matches(total - position + 1))
+        }
+        PseudoClass::Not(selector) => !matches_selector(selector, cursor),
+    }
+}
@@ -0,0 +1,143 @@
+//! A css selector engine that works on the parsed tree.
+//!
+//! Supported syntax:
+//! - Type, universal, id and class selectors: `div`, `*`, `#id`, `.class`
+//! - Attribute selectors: `[attr]`, `[attr=value]`, `[attr~=value]`, `[attr|=value]`,
+//!   `[attr^=value]`, `[attr$=value]`, `[attr*=value]` and the case-insensitive flag `[attr=value i]`
+//! - Combinators: descendant (` `), child (`>`), next sibling (`+`) and subsequent sibling (`~`)
+//! - Pseudo-classes: `:first-child`, `:last-child`, `:only-child`, `:nth-child(an+b)`,
+//!   `:nth-last-child(an+b)`, `:first-of-type`, `:last-of-type`, `:only-of-type`,
+//!   `:nth-of-type(an+b)`, `:nth-last-of-type(an+b)`, `:empty`, `:root` and `:not(selector)`
+//! - Selector lists: `h1, h2`
+
+use crate::dom::element::Element;
+use crate::dom::node::Node;
+use crate::Result;
+
+mod matching;
+mod parser;
+
+/// A parsed css selector, or list of selectors, that can be matched against elements.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Selector {
+    selectors: Vec<ComplexSelector>,
+}
+
+/// Compound selectors joined by combinators, stored in source order.
+#[derive(Debug, Clone, PartialEq)]
+struct ComplexSelector {
+    compounds: Vec<CompoundSelector>,
+    /// `combinators[i]` sits between `compounds[i]` and `compounds[i + 1]`
+    combinators: Vec<Combinator>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Combinator {
+    Descendant,
+    Child,
+    NextSibling,
+    SubsequentSibling,
+}
+
+/// A sequence of simple selectors that all have to match the same element, ex: `div.card[href]`.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct CompoundSelector {
+    /// `None` for the universal selector or if no type was given
+    name: Option<String>,
+    components: Vec<Component>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Component {
+    Id(String),
+    Class(String),
+    Attribute(AttributeSelector),
+    Pseudo(PseudoClass),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct AttributeSelector {
+    name: String,
+    operation: Option<(AttributeOperator, String)>,
+    case_insensitive: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum AttributeOperator {
+    /// `[attr=value]`
+    Equals,
+    /// `[attr~=value]`
+    Includes,
+    /// `[attr|=value]`
+    DashMatch,
+    /// `[attr^=value]`
+    Prefix,
+    /// `[attr$=value]`
+    Suffix,
+    /// `[attr*=value]`
+    Substring,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum PseudoClass {
+    Root,
+    Empty,
+    FirstChild,
+    LastChild,
+    OnlyChild,
+    FirstOfType,
+    LastOfType,
+    OnlyOfType,
+    NthChild(Nth),
+    NthLastChild(Nth),
+    NthOfType(Nth),
+    NthLastOfType(Nth),
+    Not(Selector),
+}
+
+/// The `an+b` argument of the `:nth-*` pseudo-classes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Nth {
+    a: i32,
+    b: i32,
+}
+
+impl Nth {
+    /// Checks if the 1-based `position` is part of the `an+b` sequence.
+    fn matches(self, position: usize) -> bool {
+        let offset = position as i64 - self.b as i64;
+        match self.a {
+            0 => offset == 0,
+            a => offset % a as i64 == 0 && offset / a as i64 >= 0,
+        }
+    }
+}
+
+impl Selector {
+    /// Parses a selector, or a comma separated list of selectors.
+    ///
+    /// ```rust
+    /// use html_parser::Selector;
+    ///
+    /// assert!(Selector::parse("div.card > a[href^=http]").is_ok());
+    /// assert!(Selector::parse("div >").is_err());
+    /// ```
+    pub fn parse(selector: &str) -> Result<Self> {
+        parser::parse(selector)
+    }
+
+    /// Returns all of the elements within `nodes` (at any depth) that match the selector,
+    /// in document order.
+    pub fn select<'a>(&self, nodes: &'a [Node<'a>]) -> Vec<&'a Element<'a>> {
+        matching::select(self, None, nodes)
+    }
+
+    /// Returns all of the descendants of `element` that match the selector, in document order.
+    /// The element itself is never part of the result, but it is taken into account when
+    /// matching combinators, ex: `div > p` on a `div` returns its `p` children. The siblings of
+    /// the element aren't known, so it never matches sibling combinators, `:root` or positional
+    /// pseudo-classes like `:first-child`.
+    pub fn select_in<'a>(&self, element: &'a Element<'a>) -> Vec<&'a Element<'a>> {
+        matching::select(self, Some(element), &element.children)
+    }
+}
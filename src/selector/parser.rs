@@ -0,0 +1,396 @@
+use super::{
+    AttributeOperator, AttributeSelector, Combinator, ComplexSelector, Component, CompoundSelector,
+    Nth, PseudoClass, Selector,
+};
+use crate::error::Error;
+use crate::Result;
+use std::iter::Peekable;
+use std::str::CharIndices;
+
+pub fn parse(input: &str) -> Result<Selector> {
+    let mut parser = Parser {
+        input,
+        chars: input.char_indices().peekable(),
+    };
+    let selector = parser.selector_list()?;
+    match parser.chars.peek() {
+        None => Ok(selector),
+        Some(&(_, c)) => Err(parser.error(&format!("unexpected character '{}'", c))),
+    }
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&mut self, message: &str) -> Error {
+        let position = match self.chars.peek() {
+            Some(&(index, _)) => index,
+            None => self.input.len(),
+        };
+        Error::Selector(format!(
+            "Invalid selector '{}' at position {}: {}",
+            self.input, position, message
+        ))
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.chars.peek().map(|&(_, c)| c)
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        if self.peek() == Some(expected) {
+            self.chars.next();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<()> {
+        if self.eat(expected) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", expected)))
+        }
+    }
+
+    fn skip_whitespace(&mut self) -> bool {
+        let mut skipped = false;
+        while matches!(self.peek(), Some(c) if c.is_whitespace()) {
+            self.chars.next();
+            skipped = true;
+        }
+        skipped
+    }
+
+    fn selector_list(&mut self) -> Result<Selector> {
+        let mut selectors = vec![];
+        loop {
+            self.skip_whitespace();
+            selectors.push(self.complex_selector()?);
+            self.skip_whitespace();
+            if !self.eat(',') {
+                break;
+            }
+        }
+        Ok(Selector { selectors })
+    }
+
+    fn complex_selector(&mut self) -> Result<ComplexSelector> {
+        let mut complex = ComplexSelector {
+            compounds: vec![self.compound_selector()?],
+            combinators: vec![],
+        };
+        loop {
+            let whitespace = self.skip_whitespace();
+            let combinator = match self.peek() {
+                Some('>') => Combinator::Child,
+                Some('+') => Combinator::NextSibling,
+                Some('~') => Combinator::SubsequentSibling,
+                Some(',') | Some(')') | None => break,
+                Some(_) if whitespace => Combinator::Descendant,
+                Some(c) => return Err(self.error(&format!("unexpected character '{}'", c))),
+            };
+            if combinator != Combinator::Descendant {
+                self.chars.next();
+                self.skip_whitespace();
+            }
+            complex.combinators.push(combinator);
+            complex.compounds.push(self.compound_selector()?);
+        }
+        Ok(complex)
+    }
+
+    fn compound_selector(&mut self) -> Result<CompoundSelector> {
+        let mut compound = CompoundSelector::default();
+        if self.eat('*') {
+            compound.name = None;
+        } else if self.peek().is_some_and(is_ident_start) {
+            compound.name = Some(self.ident()?);
+        } else if !matches!(self.peek(), Some('#') | Some('.') | Some('[') | Some(':')) {
+            return Err(self.error("expected a selector"));
+        }
+
+        loop {
+            let component = match self.peek() {
+                Some('#') => {
+                    self.chars.next();
+                    Component::Id(self.ident()?)
+                }
+                Some('.') => {
+                    self.chars.next();
+                    Component::Class(self.ident()?)
+                }
+                Some('[') => {
+                    self.chars.next();
+                    Component::Attribute(self.attribute()?)
+                }
+                Some(':') => {
+                    self.chars.next();
+                    Component::Pseudo(self.pseudo_class()?)
+                }
+                _ => break,
+            };
+            compound.components.push(component);
+        }
+        Ok(compound)
+    }
+
+    fn attribute(&mut self) -> Result<AttributeSelector> {
+        self.skip_whitespace();
+        let name = self.ident()?;
+        self.skip_whitespace();
+
+        let operator = match self.peek() {
+            Some(']') => {
+                self.chars.next();
+                return Ok(AttributeSelector {
+                    name,
+                    operation: None,
+                    case_insensitive: false,
+                });
+            }
+            Some('=') => None,
+            Some('~') => Some(AttributeOperator::Includes),
+            Some('|') => Some(AttributeOperator::DashMatch),
+            Some('^') => Some(AttributeOperator::Prefix),
+            Some('$') => Some(AttributeOperator::Suffix),
+            Some('*') => Some(AttributeOperator::Substring),
+            _ => return Err(self.error("expected an attribute operator or ']'")),
+        };
+        let operator = match operator {
+            Some(operator) => {
+                self.chars.next();
+                operator
+            }
+            None => AttributeOperator::Equals,
+        };
+        self.expect('=')?;
+        self.skip_whitespace();
+
+        let value = match self.peek() {
+            Some(quote) if quote == '"' || quote == '\'' => self.string(quote)?,
+            _ => self.ident()?,
+        };
+        self.skip_whitespace();
+
+        let case_insensitive = match self.peek() {
+            Some('i') | Some('I') => {
+                self.chars.next();
+                self.skip_whitespace();
+                true
+            }
+            Some('s') | Some('S') => {
+                self.chars.next();
+                self.skip_whitespace();
+                false
+            }
+            _ => false,
+        };
+        self.expect(']')?;
+
+        Ok(AttributeSelector {
+            name,
+            operation: Some((operator, value)),
+            case_insensitive,
+        })
+    }
+
+    fn pseudo_class(&mut self) -> Result<PseudoClass> {
+        let name = self.ident()?.to_ascii_lowercase();
+        let pseudo_class = match name.as_str() {
+            "root" => PseudoClass::Root,
+            "empty" => PseudoClass::Empty,
+            "first-child" => PseudoClass::FirstChild,
+            "last-child" => PseudoClass::LastChild,
+            "only-child" => PseudoClass::OnlyChild,
+            "first-of-type" => PseudoClass::FirstOfType,
+            "last-of-type" => PseudoClass::LastOfType,
+            "only-of-type" => PseudoClass::OnlyOfType,
+            "nth-child" => PseudoClass::NthChild(self.nth_argument()?),
+            "nth-last-child" => PseudoClass::NthLastChild(self.nth_argument()?),
+            "nth-of-type" => PseudoClass::NthOfType(self.nth_argument()?),
+            "nth-last-of-type" => PseudoClass::NthLastOfType(self.nth_argument()?),
+            "not" => {
+                self.expect('(')?;
+                let selector = self.selector_list()?;
+                self.expect(')')?;
+                PseudoClass::Not(selector)
+            }
+            _ => return Err(self.error(&format!("unsupported pseudo-class ':{}'", name))),
+        };
+        Ok(pseudo_class)
+    }
+
+    /// Parses `(odd)`, `(even)` and `(an+b)`
+    fn nth_argument(&mut self) -> Result<Nth> {
+        self.expect('(')?;
+        self.skip_whitespace();
+
+        let mut argument = String::new();
+        while let Some(c) = self.peek() {
+            if c == ')' {
+                break;
+            }
+            if !c.is_whitespace() {
+                argument.push(c.to_ascii_lowercase());
+            }
+            self.chars.next();
+        }
+        self.expect(')')?;
+
+        let nth = match argument.as_str() {
+            "odd" => Some(Nth { a: 2, b: 1 }),
+            "even" => Some(Nth { a: 2, b: 0 }),
+            argument => parse_nth(argument),
+        };
+        nth.ok_or_else(|| self.error(&format!("invalid nth argument '{}'", argument)))
+    }
+
+    fn ident(&mut self) -> Result<String> {
+        let mut ident = String::new();
+        while let Some(c) = self.peek() {
+            if c == '\\' {
+                self.chars.next();
+                ident.push(self.escape()?);
+            } else if is_ident_char(c) {
+                self.chars.next();
+                ident.push(c);
+            } else {
+                break;
+            }
+        }
+        if ident.is_empty() {
+            return Err(self.error("expected an identifier"));
+        }
+        Ok(ident)
+    }
+
+    fn string(&mut self, quote: char) -> Result<String> {
+        self.chars.next();
+        let mut string = String::new();
+        loop {
+            match self.chars.next() {
+                Some((_, c)) if c == quote => return Ok(string),
+                Some((_, '\\')) => string.push(self.escape()?),
+                Some((_, c)) => string.push(c),
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+    }
+
+    /// Parses the character after a backslash, either a literal character or a hex escape
+    fn escape(&mut self) -> Result<char> {
+        let mut hex = String::new();
+        while hex.len() < 6 {
+            match self.peek() {
+                Some(c) if c.is_ascii_hexdigit() => {
+                    hex.push(c);
+                    self.chars.next();
+                }
+                _ => break,
+            }
+        }
+        if hex.is_empty() {
+            return match self.chars.next() {
+                Some((_, c)) => Ok(c),
+                None => Err(self.error("unexpected end of escape")),
+            };
+        }
+        // A single whitespace terminates a hex escape
+        if matches!(self.peek(), Some(c) if c.is_whitespace()) {
+            self.chars.next();
+        }
+        // Zero, surrogates and code points past the last one are replaced, as in css
+        let code = u32::from_str_radix(&hex, 16).expect("hex digits");
+        match std::char::from_u32(code) {
+            Some(c) if c != '\0' => Ok(c),
+            _ => Ok('\u{fffd}'),
+        }
+    }
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_' || c == '-' || c == '\\' || !c.is_ascii()
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '-' || !c.is_ascii()
+}
+
+/// Parses the `an+b` microsyntax, with whitespace already removed
+fn parse_nth(argument: &str) -> Option<Nth> {
+    match argument.find('n') {
+        Some(index) => {
+            let a = match &argument[..index] {
+                "" | "+" => 1,
+                "-" => -1,
+                a => a.parse().ok()?,
+            };
+            let b = match &argument[index + 1..] {
+                "" => 0,
+                // The sign after the `+` would be accepted by `parse`, ex: `2n++1`
+                b if b.starts_with('+') && !b[1..].starts_with(['+', '-']) => {
+                    b[1..].parse().ok()?
+                }
+                b if b.starts_with('-') => b.parse().ok()?,
+                _ => return None,
+            };
+            Some(Nth { a, b })
+        }
+        None => Some(Nth {
+            a: 0,
+            b: argument.parse().ok()?,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_can_parse_nth_arguments() {
+        assert_eq!(parse_nth("2n+1"), Some(Nth { a: 2, b: 1 }));
+        assert_eq!(parse_nth("-n+3"), Some(Nth { a: -1, b: 3 }));
+        assert_eq!(parse_nth("n"), Some(Nth { a: 1, b: 0 }));
+        assert_eq!(parse_nth("3n-2"), Some(Nth { a: 3, b: -2 }));
+        assert_eq!(parse_nth("5"), Some(Nth { a: 0, b: 5 }));
+        assert_eq!(parse_nth("2n+"), None);
+        assert_eq!(parse_nth("2n++1"), None);
+        assert_eq!(parse_nth("2n+-1"), None);
+        assert_eq!(parse_nth("2n-+1"), None);
+        assert_eq!(parse_nth("x"), None);
+    }
+
+    #[test]
+    fn it_can_parse_escapes() {
+        let escape = |input| {
+            let mut parser = Parser {
+                input,
+                chars: input.char_indices().peekable(),
+            };
+            parser.escape().ok()
+        };
+        assert_eq!(escape("31 "), Some('1'));
+        assert_eq!(escape("."), Some('.'));
+        assert_eq!(escape("0"), Some('\u{fffd}'));
+        assert_eq!(escape("d800"), Some('\u{fffd}'));
+        assert_eq!(escape("110000"), Some('\u{fffd}'));
+        assert_eq!(escape(""), None);
+    }
+
+    #[test]
+    fn it_rejects_invalid_selectors() {
+        assert!(parse("").is_err());
+        assert!(parse("div >").is_err());
+        assert!(parse("div,").is_err());
+        assert!(parse("[href").is_err());
+        assert!(parse(":hover").is_err());
+        assert!(parse(":nth-child(foo)").is_err());
+        assert!(parse(":nth-child(2n++1)").is_err());
+    }
+}
@@ -0,0 +1,524 @@
+use std::collections::HashSet;
+
+use super::{Span, Tag, TagAttribute, Token};
+use crate::dom::formatting;
+use crate::error::ParseError;
+
+/// A hand-written tokenizer that gives the same tokens as the pest grammar in
+/// `grammar/rules.pest`, in time linear in the size of the input.
+///
+/// The grammar tries every alternative at every `<`, and a start tag without an end, ex:
+/// `<a b=<a b=<a b=...`, is read again from every `<` in it. Here a failed start tag remembers
+/// where its attributes started, and the searches for the end of quotes, comments and the like
+/// remember what they found, so that no part of the input is read more than a few times.
+pub(crate) struct Lexer<'a> {
+    input: &'a str,
+    bytes: &'a [u8],
+    position: usize,
+    /// Markup that starts with `<!` but isn't a token is a bogus comment instead of an error
+    lenient: bool,
+    /// The token that ended the text that was returned last
+    pending: Option<Token<'a>>,
+    /// The last search for each pattern
+    found: [Option<Found>; PATTERNS],
+    /// Positions where a list of attributes starts that isn't followed by the end of a tag
+    failed_attributes: HashSet<usize>,
+}
+
+/// The result of a search from `from`, which is still the result of a search from any position
+/// between `from` and the match
+#[derive(Clone, Copy)]
+struct Found {
+    from: usize,
+    at: Option<usize>,
+}
+
+#[derive(Clone, Copy)]
+enum Pattern {
+    Chevron,
+    Quote,
+    Apostrophe,
+    Whitespace,
+    QuestionChevron,
+    CommentEnd,
+    CdataEnd,
+    ConditionEnd,
+}
+
+const PATTERNS: usize = 8;
+
+const RAW_TEXT_ELEMENTS: [&str; 4] = ["style", "script", "title", "textarea"];
+
+fn is_whitespace(byte: u8) -> bool {
+    matches!(byte, b' ' | b'\t' | b'\r' | b'\n')
+}
+
+/// The characters of element names and attribute keys, after the first letter
+fn is_name(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || matches!(byte, b'_' | b'-' | b':')
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(input: &'a str, lenient: bool) -> Self {
+        Self {
+            input,
+            bytes: input.as_bytes(),
+            position: 0,
+            lenient,
+            pending: None,
+            found: [None; PATTERNS],
+            failed_attributes: HashSet::new(),
+        }
+    }
+
+    fn starts_with(&self, position: usize, prefix: &str) -> bool {
+        self.bytes
+            .get(position..)
+            .is_some_and(|rest| rest.starts_with(prefix.as_bytes()))
+    }
+
+    fn starts_with_ignore_case(&self, position: usize, prefix: &str) -> bool {
+        self.bytes
+            .get(position..position + prefix.len())
+            .is_some_and(|rest| rest.eq_ignore_ascii_case(prefix.as_bytes()))
+    }
+
+    fn skip_whitespace(&self, mut position: usize) -> usize {
+        while self.bytes.get(position).is_some_and(|x| is_whitespace(*x)) {
+            position += 1;
+        }
+        position
+    }
+
+    fn span(&self, start: usize, end: usize) -> Span<'a> {
+        Span::new(self.input, start, end)
+    }
+
+    fn find(&mut self, pattern: Pattern, from: usize) -> Option<usize> {
+        if let Some(found) = self.found[pattern as usize] {
+            if found.from <= from && found.at.map_or(true, |at| from <= at) {
+                return found.at;
+            }
+        }
+        let rest = &self.input[from..];
+        let at = match pattern {
+            Pattern::Chevron => rest.find('>'),
+            Pattern::Quote => rest.find('"'),
+            Pattern::Apostrophe => rest.find('\''),
+            Pattern::Whitespace => rest.find([' ', '\t', '\r', '\n']),
+            Pattern::QuestionChevron => rest.find("?>"),
+            Pattern::CommentEnd => rest.find("-->"),
+            Pattern::CdataEnd => rest.find("]]>"),
+            Pattern::ConditionEnd => rest.find("]>"),
+        }
+        .map(|index| from + index);
+        self.found[pattern as usize] = Some(Found { from, at });
+        at
+    }
+
+    /// Reads the token at the current position, in the order of the alternatives of the grammar
+    fn token(&mut self) -> Result<Token<'a>, ParseError> {
+        let start = self.position;
+        // Doctypes and comments include the whitespace before them
+        let markup = self.skip_whitespace(start);
+        if self.starts_with(markup, "<!") {
+            let token = self
+                .doctype(start, markup)
+                .or_else(|| self.conditional_comment_start(start, markup))
+                .or_else(|| self.conditional_comment_end(start))
+                .or_else(|| self.comment(start, markup))
+                .or_else(|| self.cdata(start));
+            match token {
+                Some(token) => return Ok(token),
+                None if markup == start && self.lenient => return Ok(self.bogus_comment(start)),
+                None if markup == start => {
+                    return Err(formatting::unexpected_markup(self.input, start + 1))
+                }
+                None => (),
+            }
+        }
+        let token = match self.bytes.get(start..start + 2) {
+            Some(b"<?") => self.processing_instruction(start),
+            Some(b"</") => self.end_tag(start),
+            Some([b'<', _]) => self.start_tag(start).map(|tag| self.start_tag_token(tag)),
+            _ => None,
+        };
+        Ok(token.unwrap_or_else(|| self.text(start)))
+    }
+
+    fn doctype(&mut self, start: usize, markup: usize) -> Option<Token<'a>> {
+        if !self.starts_with_ignore_case(markup + 2, "doctype") {
+            return None;
+        }
+        let content = markup + 9;
+        let end = self.find(Pattern::Chevron, content)?;
+        Some(Token::Doctype {
+            span: self.span(start, end + 1),
+            content: &self.input[content..end],
+        })
+    }
+
+    fn conditional_comment_start(&mut self, start: usize, markup: usize) -> Option<Token<'a>> {
+        if !self.starts_with(markup, "<!--") {
+            return None;
+        }
+        let bracket = self.skip_whitespace(markup + 4);
+        if !self.starts_with(bracket, "[") || !self.starts_with_ignore_case(bracket + 1, "if") {
+            return None;
+        }
+        let condition = bracket + 3;
+        let condition_end = self.find(Pattern::ConditionEnd, condition)?;
+        let mut end = condition_end + 2;
+
+        // Ex: the `<!-->` in `<!--[if !mso]><!-->`
+        let mut revealed = false;
+        if self.starts_with(end, "<!--") {
+            let marker = self.skip_whitespace(end + 4);
+            if self.starts_with(marker, ">") {
+                (end, revealed) = (marker + 1, true);
+            } else if self.starts_with(marker, "-->") {
+                (end, revealed) = (marker + 3, true);
+            }
+        }
+        Some(Token::ConditionalCommentStart {
+            span: self.span(start, end),
+            condition: &self.input[condition..condition_end],
+            revealed,
+        })
+    }
+
+    fn conditional_comment_end(&mut self, start: usize) -> Option<Token<'a>> {
+        let mut position = start;
+        if self.starts_with(position, "<!--") {
+            position = self.skip_whitespace(position + 4);
+        }
+        if !self.starts_with(position, "<![")
+            || !self.starts_with_ignore_case(position + 3, "endif")
+            || !self.starts_with(position + 8, "]")
+        {
+            return None;
+        }
+        let position = self.skip_whitespace(position + 9);
+        if !self.starts_with(position, "-->") {
+            return None;
+        }
+        let end = self.skip_whitespace(position + 3);
+        Some(Token::ConditionalCommentEnd(self.span(start, end)))
+    }
+
+    fn comment(&mut self, start: usize, markup: usize) -> Option<Token<'a>> {
+        if !self.starts_with(markup, "<!--") {
+            return None;
+        }
+        let value = self.skip_whitespace(markup + 4);
+        let comment_end = self.find(Pattern::CommentEnd, value)?;
+        let mut value_end = comment_end;
+        while value_end > value && is_whitespace(self.bytes[value_end - 1]) {
+            value_end -= 1;
+        }
+        // Comments also include the whitespace after them
+        let end = self.skip_whitespace(comment_end + 3);
+        Some(Token::Comment {
+            span: self.span(start, end),
+            value: &self.input[value..value_end],
+        })
+    }
+
+    fn cdata(&mut self, start: usize) -> Option<Token<'a>> {
+        if !self.starts_with(start, "<![CDATA[") {
+            return None;
+        }
+        let content = start + 9;
+        let end = self.find(Pattern::CdataEnd, content)?;
+        Some(Token::Cdata {
+            span: self.span(start, end + 3),
+            content: &self.input[content..end],
+        })
+    }
+
+    /// A comment without an end goes to the end of the input, other bogus comments to the next `>`
+    fn bogus_comment(&mut self, start: usize) -> Token<'a> {
+        if self.starts_with(start + 2, "--") {
+            let value = self.skip_whitespace(start + 4);
+            return Token::BogusComment {
+                span: self.span(start, self.input.len()),
+                value: &self.input[value..],
+                unclosed: true,
+            };
+        }
+        let value = start + 2;
+        let (value_end, end) = match self.find(Pattern::Chevron, value) {
+            Some(chevron) => (chevron, chevron + 1),
+            None => (self.input.len(), self.input.len()),
+        };
+        Token::BogusComment {
+            span: self.span(start, end),
+            value: &self.input[value..value_end],
+            unclosed: false,
+        }
+    }
+
+    fn processing_instruction(&mut self, start: usize) -> Option<Token<'a>> {
+        // The target goes to the first whitespace or `?>`, and the data to the first `?>`
+        let target = start + 2;
+        let end = self.find(Pattern::QuestionChevron, target)?;
+        let target_end = match self.find(Pattern::Whitespace, target) {
+            Some(whitespace) if whitespace < end => whitespace,
+            _ => end,
+        };
+        if target_end == target {
+            return None;
+        }
+        let data = self.skip_whitespace(target_end);
+        let end = self.find(Pattern::QuestionChevron, data)?;
+        Some(Token::ProcessingInstruction {
+            span: self.span(start, end + 2),
+            target: &self.input[target..target_end],
+            data: &self.input[data..end],
+        })
+    }
+
+    /// An element name, or an attribute key, which starts with a letter
+    fn name(&self, start: usize) -> Option<usize> {
+        if !self.bytes.get(start)?.is_ascii_alphabetic() {
+            return None;
+        }
+        let mut end = start + 1;
+        while self.bytes.get(end).is_some_and(|x| is_name(*x)) {
+            end += 1;
+        }
+        Some(end)
+    }
+
+    fn end_tag(&mut self, start: usize) -> Option<Token<'a>> {
+        let name = self.skip_whitespace(start + 2);
+        let name_end = self.name(name)?;
+        let end = self.skip_whitespace(name_end);
+        if !self.starts_with(end, ">") {
+            return None;
+        }
+        Some(Token::EndTag {
+            span: self.span(start, end + 1),
+            name: &self.input[name..name_end],
+        })
+    }
+
+    fn start_tag(&mut self, start: usize) -> Option<Tag<'a>> {
+        let name = self.skip_whitespace(start + 1);
+        let name_end = self.name(name)?;
+        let first_attribute = self.skip_whitespace(name_end);
+
+        let mut position = first_attribute;
+        let mut attributes = vec![];
+        loop {
+            // A list of attributes that failed before fails again
+            if !self.failed_attributes.is_empty() && self.failed_attributes.contains(&position) {
+                break;
+            }
+            match self.attribute(position) {
+                Some(attribute) => {
+                    position = attribute.span.end();
+                    attributes.push(attribute);
+                }
+                None => break,
+            }
+        }
+
+        let end = self.skip_whitespace(position);
+        let (end, self_closing) = match self.bytes.get(end..end + 2) {
+            Some([b'>', _]) => (end + 1, false),
+            Some(b"/>") => (end + 2, true),
+            None if self.starts_with(end, ">") => (end + 1, false),
+            _ => {
+                self.failed_attributes.insert(first_attribute);
+                self.failed_attributes
+                    .extend(attributes.iter().map(|attribute| attribute.span.end()));
+                return None;
+            }
+        };
+        Some(Tag {
+            span: self.span(start, end),
+            name: &self.input[name..name_end],
+            attributes,
+            self_closing,
+        })
+    }
+
+    fn attribute(&mut self, start: usize) -> Option<TagAttribute<'a>> {
+        let key = self.skip_whitespace(start);
+        let key_end = self.skip_whitespace(self.name(key)?);
+        let mut end = key_end;
+        let mut value = None;
+        if self.starts_with(key_end, "=") {
+            let value_start = self.skip_whitespace(key_end + 1);
+            match self.bytes.get(value_start) {
+                // An attribute with a quote but no end of it has no value
+                Some(b'"') | Some(b'\'') => {
+                    let pattern = match self.bytes[value_start] {
+                        b'"' => Pattern::Quote,
+                        _ => Pattern::Apostrophe,
+                    };
+                    if let Some(quote) = self.find(pattern, value_start + 1) {
+                        value = Some(self.span(value_start + 1, quote));
+                        end = quote + 1;
+                    }
+                }
+                // An unquoted value goes to the first whitespace, `>`, `/>` or `?>`
+                _ => {
+                    let mut value_end = value_start;
+                    while let Some(x) = self.bytes.get(value_end) {
+                        let chevron = self.bytes.get(value_end + 1) == Some(&b'>');
+                        if is_whitespace(*x) || *x == b'>' || (matches!(x, b'/' | b'?') && chevron)
+                        {
+                            break;
+                        }
+                        value_end += 1;
+                    }
+                    value = Some(self.span(value_start, value_end));
+                    end = value_end;
+                }
+            }
+        }
+        Some(TagAttribute {
+            span: self.span(start, end),
+            key: self.span(start, key_end),
+            value,
+        })
+    }
+
+    /// A start tag of an element with raw text, that isn't self-closing, includes the content
+    /// and end tag
+    fn start_tag_token(&mut self, mut tag: Tag<'a>) -> Token<'a> {
+        let raw_text = RAW_TEXT_ELEMENTS
+            .iter()
+            .any(|name| tag.name.eq_ignore_ascii_case(name));
+        if !raw_text || tag.self_closing {
+            return Token::StartTag(tag);
+        }
+        let content = self.skip_whitespace(tag.span.end());
+        tag.span = self.span(tag.span.start(), content);
+
+        // The content goes to the whitespace before the first end tag with the same name, in the
+        // same case
+        let mut position = content;
+        let mut end_tag = None;
+        while let Some(index) = self.input[position..].find("</") {
+            let name = self.skip_whitespace(position + index + 2);
+            if self.starts_with(name, tag.name) {
+                let end = self.skip_whitespace(name + tag.name.len());
+                if self.starts_with(end, ">") {
+                    let mut start = position + index;
+                    while start > content && is_whitespace(self.bytes[start - 1]) {
+                        start -= 1;
+                    }
+                    end_tag = Some(self.span(start, end + 1));
+                    break;
+                }
+            }
+            position += index + 2;
+        }
+        let content_end = end_tag.map_or(self.input.len(), |end_tag| end_tag.start());
+        Token::RawText {
+            start_tag: tag,
+            content: self.span(content, content_end),
+            end_tag,
+        }
+    }
+
+    /// Text goes to the first start tag, end tag, processing instruction or `<!`, and the token
+    /// that ends it is kept for the next call
+    fn text(&mut self, start: usize) -> Token<'a> {
+        let first = self.input[start..].chars().next().map_or(1, char::len_utf8);
+        let mut position = start + first;
+        while let Some(index) = self.input[position..].find('<') {
+            let chevron = position + index;
+            let token = match self.bytes.get(chevron + 1) {
+                Some(b'!') => return Token::Text(self.span(start, chevron)),
+                Some(b'?') => self.processing_instruction(chevron),
+                Some(b'/') => self.end_tag(chevron),
+                _ => self.start_tag(chevron).map(|tag| self.start_tag_token(tag)),
+            };
+            if let Some(token) = token {
+                self.pending = Some(token);
+                return Token::Text(self.span(start, chevron));
+            }
+            position = chevron + 1;
+        }
+        Token::Text(self.span(start, self.input.len()))
+    }
+}
+
+/// The end of a token
+fn token_end(token: &Token) -> usize {
+    match token {
+        Token::Doctype { span, .. }
+        | Token::Text(span)
+        | Token::Comment { span, .. }
+        | Token::BogusComment { span, .. }
+        | Token::Cdata { span, .. }
+        | Token::ProcessingInstruction { span, .. }
+        | Token::EndTag { span, .. }
+        | Token::ConditionalCommentStart { span, .. }
+        | Token::ConditionalCommentEnd(span) => span.end(),
+        Token::StartTag(tag) => tag.span.end(),
+        Token::RawText {
+            content, end_tag, ..
+        } => end_tag.map_or(content.end(), |end_tag| end_tag.end()),
+    }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Result<Token<'a>, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let token = match self.pending.take() {
+            Some(token) => token,
+            None if self.position < self.input.len() => match self.token() {
+                Ok(token) => token,
+                Err(error) => {
+                    // Nothing is read after an error
+                    self.position = self.input.len();
+                    return Some(Err(error));
+                }
+            },
+            None => return None,
+        };
+        self.position = token_end(&token);
+        Some(Ok(token))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    fn tokens(input: &str) -> Vec<Token<'_>> {
+        Lexer::new(input, false).map(Result::unwrap).collect()
+    }
+
+    #[test]
+    fn it_keeps_the_end_of_text_as_the_next_token() {
+        let tokens = tokens("a<b>c</b>");
+        assert!(matches!(tokens[0], Token::Text(span) if span.as_str() == "a"));
+        assert!(matches!(&tokens[1], Token::StartTag(tag) if tag.name == "b"));
+        assert!(matches!(tokens[2], Token::Text(span) if span.as_str() == "c"));
+        assert!(matches!(tokens[3], Token::EndTag { name: "b", .. }));
+        assert_eq!(tokens.len(), 4);
+    }
+
+    #[test]
+    fn it_reads_unclosed_start_tags_as_text() {
+        let tokens = tokens("<a b=<a b='x");
+        assert!(matches!(tokens[..], [Token::Text(span)] if span.as_str() == "<a b=<a b='x"));
+    }
+
+    #[test]
+    fn it_reads_unclosed_start_tags_in_linear_time() {
+        // Every `<` starts a start tag that goes to the end of the input
+        let input = "<a b=".repeat(100_000);
+        let start = Instant::now();
+        assert_eq!(tokens(&input).len(), 1);
+        assert!(start.elapsed() < Duration::from_secs(2));
+    }
+}
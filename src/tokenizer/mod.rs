@@ -0,0 +1,131 @@
+//! The tokens that the events, and the tree, are built from.
+//!
+//! The input is split into a flat list of tokens, where start and end tags are separate tokens,
+//! by the hand-written [`Lexer`], or by the pest grammar with the `pest` feature. Both give the
+//! same tokens for the same input, see [`Tokenizer`](crate::Tokenizer).
+
+mod lexer;
+
+pub(crate) use lexer::Lexer;
+
+/// A part of the input, with byte offsets
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Span<'a> {
+    input: &'a str,
+    start: usize,
+    end: usize,
+}
+
+impl<'a> Span<'a> {
+    pub fn new(input: &'a str, start: usize, end: usize) -> Self {
+        Self { input, start, end }
+    }
+
+    pub fn as_str(&self) -> &'a str {
+        &self.input[self.start..self.end]
+    }
+
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    pub fn end(&self) -> usize {
+        self.end
+    }
+
+    /// Removes leading and trailing whitespace
+    pub fn trim(self) -> Self {
+        let text = self.as_str();
+        let start = text.len() - text.trim_start().len();
+        let end = text.trim_end().len().max(start);
+        Self::new(self.input, self.start + start, self.start + end)
+    }
+}
+
+/// A token of the input. The spans are the ones of the grammar, and may include whitespace
+/// around the token, ex: comments include the whitespace before and after them.
+#[derive(Debug)]
+pub(crate) enum Token<'a> {
+    /// Ex: `<!doctype html>`, where the content is the part after `doctype`
+    Doctype {
+        span: Span<'a>,
+        content: &'a str,
+    },
+    Text(Span<'a>),
+    Comment {
+        span: Span<'a>,
+        value: &'a str,
+    },
+    /// Markup that starts with `<!` but isn't any of the other tokens, which is only a token in
+    /// lenient mode. Ex: `<!x>` and `<!-- text` without an end
+    BogusComment {
+        span: Span<'a>,
+        value: &'a str,
+        unclosed: bool,
+    },
+    /// Ex: `<![CDATA[ x < y ]]>`
+    Cdata {
+        span: Span<'a>,
+        content: &'a str,
+    },
+    /// Ex: `<?xml version="1.0"?>`, where the data is empty if there is none
+    ProcessingInstruction {
+        span: Span<'a>,
+        target: &'a str,
+        data: &'a str,
+    },
+    StartTag(Tag<'a>),
+    EndTag {
+        span: Span<'a>,
+        name: &'a str,
+    },
+    /// An element with raw text, ex: `<script>`, with its start tag, content and end tag. Without
+    /// an end tag the content goes to the end of the input.
+    RawText {
+        start_tag: Tag<'a>,
+        content: Span<'a>,
+        end_tag: Option<Span<'a>>,
+    },
+    /// Ex: `<!--[if mso]>`, and `<!--[if !mso]><!-->` where the content is revealed
+    ConditionalCommentStart {
+        span: Span<'a>,
+        condition: &'a str,
+        revealed: bool,
+    },
+    /// Ex: `<![endif]-->`
+    ConditionalCommentEnd(Span<'a>),
+}
+
+impl<'a> Token<'a> {
+    /// The span of the token, which is the one of the start tag for raw text elements
+    pub fn span(&self) -> Span<'a> {
+        match self {
+            Token::Doctype { span, .. }
+            | Token::Comment { span, .. }
+            | Token::BogusComment { span, .. }
+            | Token::Cdata { span, .. }
+            | Token::ProcessingInstruction { span, .. }
+            | Token::EndTag { span, .. }
+            | Token::ConditionalCommentStart { span, .. } => *span,
+            Token::Text(span) | Token::ConditionalCommentEnd(span) => *span,
+            Token::StartTag(tag) | Token::RawText { start_tag: tag, .. } => tag.span,
+        }
+    }
+}
+
+/// A start tag, ex: `<div class="a">` and `<br/>`
+#[derive(Debug)]
+pub(crate) struct Tag<'a> {
+    pub span: Span<'a>,
+    pub name: &'a str,
+    pub attributes: Vec<TagAttribute<'a>>,
+    pub self_closing: bool,
+}
+
+/// An attribute of a start tag, where the value is the part between the quotes
+#[derive(Debug)]
+pub(crate) struct TagAttribute<'a> {
+    pub span: Span<'a>,
+    pub key: Span<'a>,
+    pub value: Option<Span<'a>>,
+}
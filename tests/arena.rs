@@ -0,0 +1,94 @@
+use html_parser::{ArenaDom, Dom, NodeId, Result};
+use indoc::indoc;
+
+fn dom() -> Dom<'static> {
+    let html = indoc!(
+        r#"
+        <!--start-->
+        <form id="login">
+            <div>
+                <label>User</label>
+                <input name="user">
+            </div>
+            <button>Login</button>
+        </form>
+        text
+    "#
+    );
+    Dom::parse(html).unwrap()
+}
+
+fn names(arena: &ArenaDom, ids: impl Iterator<Item = NodeId>) -> Vec<String> {
+    ids.map(|id| match arena[id].element() {
+        Some(element) => element.name.to_string(),
+        None => format!("#{}", arena[id].to_html()),
+    })
+    .collect()
+}
+
+#[test]
+fn it_can_navigate_the_arena() -> Result<()> {
+    let arena = dom().into_arena();
+    let input = arena
+        .iter()
+        .find(|id| arena[*id].element().is_some_and(|e| e.name == "input"))
+        .unwrap();
+
+    assert_eq!(names(&arena, arena.ancestors(input)), vec!["div", "form"]);
+    assert_eq!(
+        names(&arena, arena.prev_sibling(input).into_iter()),
+        vec!["label"]
+    );
+    assert_eq!(arena.next_sibling(input), None);
+
+    let form = arena.parent(arena.parent(input).unwrap()).unwrap();
+    assert_eq!(arena.parent(form), None);
+    assert_eq!(names(&arena, arena.children(form)), vec!["div", "button"]);
+    assert_eq!(
+        names(&arena, arena.descendants(form)),
+        vec!["div", "label", "#User", "input", "button", "#Login"]
+    );
+    assert_eq!(
+        names(&arena, arena.roots()),
+        vec!["#<!--start-->", "form", "#\ntext\n"]
+    );
+    assert_eq!(arena.len(), 9);
+    Ok(())
+}
+
+#[test]
+fn it_stores_the_arena_in_document_order() -> Result<()> {
+    let arena = dom().into_arena();
+    let ids = arena.iter().collect::<Vec<_>>();
+    assert_eq!(ids.len(), arena.len());
+    assert!(ids
+        .iter()
+        .enumerate()
+        .all(|(index, id)| id.index() == index));
+    Ok(())
+}
+
+#[test]
+fn it_can_convert_the_arena_back_to_a_dom() -> Result<()> {
+    let dom = dom();
+    let mut arena = dom.clone().into_arena();
+    assert_eq!(arena.clone().into_dom(), dom);
+
+    let button = arena
+        .iter()
+        .find(|id| arena[*id].element().is_some_and(|e| e.name == "button"))
+        .unwrap();
+    arena[button].element_mut().unwrap().id = Some("submit".into());
+    let dom = Dom::from(arena);
+    assert_eq!(dom.select("#submit")?.len(), 1);
+    Ok(())
+}
+
+#[test]
+fn it_can_convert_an_empty_dom() -> Result<()> {
+    let arena = Dom::parse("")?.into_arena();
+    assert!(arena.is_empty());
+    assert_eq!(arena.iter().count(), 0);
+    assert_eq!(arena.into_dom(), Dom::parse("")?);
+    Ok(())
+}
@@ -5,7 +5,7 @@ use std::process::Command;
 use tempfile::NamedTempFile;
 
 #[test]
-fn it_prints_out_processing_error() -> Result<()> {
+fn it_prints_out_processing_instructions() -> Result<()> {
     let html = indoc!(
         r#"
             <?xml version="1.0" ?>
@@ -24,6 +24,9 @@ fn it_prints_out_processing_error() -> Result<()> {
 
     let stdout = String::from_utf8(output.stdout).unwrap();
 
-    assert!(stdout.starts_with("# Failed to create element at rule: el_process_instruct"));
+    assert!(!stdout.starts_with('#'));
+    assert!(stdout.contains(
+        r#"{"type":"processingInstruction","target":"xml","data":"version=\"1.0\" "}"#
+    ));
     Ok(())
 }
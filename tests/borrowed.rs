@@ -0,0 +1,75 @@
+use html_parser::{Dom, Node, ParseOptions, Result, TagCase};
+use std::borrow::Cow;
+
+// Takes the Cow itself, as its variant is what is tested
+#[allow(clippy::ptr_arg)]
+fn is_borrowed(value: &Cow<str>) -> bool {
+    matches!(value, Cow::Borrowed(_))
+}
+
+#[test]
+fn it_borrows_from_the_input() -> Result<()> {
+    let dom = Dom::parse("<div id=\"a\" class=\"b c\" data-x=\"1\">text<!--comment--></div>")?;
+    let div = dom.children[0].element().unwrap();
+    assert!(is_borrowed(&div.name));
+    assert!(is_borrowed(div.id.as_ref().unwrap()));
+    assert!(div.classes.iter().all(is_borrowed));
+    let attribute = div.attributes.get_attribute("data-x").unwrap();
+    assert!(is_borrowed(&attribute.key));
+    assert!(is_borrowed(attribute.value.as_ref().unwrap()));
+    match &div.children[..] {
+        [Node::Text(text), Node::Comment(comment)] => {
+            assert!(is_borrowed(&text.value));
+            assert!(is_borrowed(&comment.value));
+        }
+        children => panic!("unexpected children: {:?}", children),
+    }
+    assert!(is_borrowed(&dom.source));
+    Ok(())
+}
+
+#[test]
+fn it_owns_what_had_to_be_decoded() -> Result<()> {
+    let dom = Dom::parse("<a title=\"a &amp; b\" class=\"x&#32;y\">1 &lt; 2</a>")?;
+    let a = dom.children[0].element().unwrap();
+    assert!(!is_borrowed(a.attributes["title"].as_ref().unwrap()));
+    assert!(a.classes.iter().all(|class| !is_borrowed(class)));
+    assert_eq!(a.classes, vec!["x", "y"]);
+    match &a.children[0] {
+        Node::Text(text) => assert!(!is_borrowed(&text.value)),
+        child => panic!("unexpected child: {:?}", child),
+    }
+
+    let options = ParseOptions::new().tag_case(TagCase::Lowercase);
+    let dom = Dom::parse_with("<DIV></DIV><span></span>", &options)?;
+    assert!(!is_borrowed(&dom.children[0].element().unwrap().name));
+    assert!(is_borrowed(&dom.children[1].element().unwrap().name));
+    Ok(())
+}
+
+#[test]
+fn it_can_detach_the_tree_from_the_input() -> Result<()> {
+    let dom = {
+        let html = String::from("<p class=\"a\">one</p><!--two-->");
+        Dom::parse(&html)?.into_owned()
+    };
+    assert_eq!(dom.to_html(), "<p class=\"a\">one</p><!--two-->");
+    assert_eq!(
+        dom.span_text(&dom.children[0].element().unwrap().source_span),
+        "<p class=\"a\">one</p>"
+    );
+    Ok(())
+}
+
+#[test]
+fn it_detaches_text_that_is_changed() -> Result<()> {
+    let html = "<p>text</p>";
+    let mut dom = Dom::parse(html)?;
+    dom.walk_mut(|node| {
+        if let Some(text) = node.text_mut() {
+            text.push('!');
+        }
+    });
+    assert_eq!(dom.to_html(), "<p>text!</p>");
+    Ok(())
+}
@@ -0,0 +1,62 @@
+use html_parser::{CdataSections, Dom, Node, ParseOptions, Result};
+
+#[test]
+fn it_keeps_cdata_sections_in_svg_and_math() -> Result<()> {
+    let html = "<svg><style><![CDATA[ a > b ]]></style><text><![CDATA[x < y & z]]></text></svg><math><![CDATA[1<2]]></math>";
+    let dom = Dom::parse(html)?;
+    let text = dom.select("text")?[0];
+    match &text.children[0] {
+        Node::Cdata(cdata) => {
+            assert_eq!(cdata.as_str(), "x < y & z");
+            assert_eq!(dom.span_text(&cdata.source_span), "<![CDATA[x < y & z]]>");
+        }
+        node => panic!("expected a cdata section, got {:?}", node),
+    }
+    let math = dom.select("math")?[0];
+    assert!(matches!(&math.children[0], Node::Cdata(cdata) if cdata.as_str() == "1<2"));
+    assert_eq!(dom.to_html(), html);
+    Ok(())
+}
+
+#[test]
+fn it_parses_cdata_sections_as_comments_in_html() -> Result<()> {
+    let html = "<div><![CDATA[x]]><svg><foreignObject><![CDATA[y]]></foreignObject></svg></div>";
+    let dom = Dom::parse(html)?;
+    let div = dom.children[0].element().unwrap();
+    assert_eq!(div.children[0].comment(), Some("[CDATA[x]]"));
+    let foreign_object = dom.select("foreignObject")?[0];
+    assert_eq!(foreign_object.children[0].comment(), Some("[CDATA[y]]"));
+    Ok(())
+}
+
+#[test]
+fn it_keeps_cdata_sections_everywhere_in_xhtml() -> Result<()> {
+    let options = ParseOptions::new().xhtml(true);
+    let dom = Dom::parse_with("<![CDATA[a]]><p><![CDATA[b]]></p>", &options)?;
+    assert!(matches!(&dom.children[0], Node::Cdata(cdata) if cdata.as_str() == "a"));
+    let p = dom.children[1].element().unwrap();
+    assert!(matches!(&p.children[0], Node::Cdata(cdata) if cdata.as_str() == "b"));
+    Ok(())
+}
+
+#[test]
+fn it_can_keep_cdata_sections_as_text() -> Result<()> {
+    let options = ParseOptions::new().cdata_sections(CdataSections::Text);
+    let dom = Dom::parse_with("<svg><text><![CDATA[x < y &amp;]]></text></svg>", &options)?;
+    let text = dom.select("text")?[0];
+    assert_eq!(text.children[0].text(), Some("x < y &amp;"));
+    assert_eq!(dom.to_html(), "<svg><text>x &lt; y &amp;amp;</text></svg>");
+    Ok(())
+}
+
+#[test]
+fn it_can_serialize_cdata_sections() -> Result<()> {
+    let dom = Dom::parse("<svg><![CDATA[x]]></svg>")?;
+    let json = dom.to_json()?;
+    assert!(json.contains(r#"{"type":"cdata","value":"x"}"#));
+    let svg = Dom::from_json(&json)?.children.remove(0);
+    assert!(
+        matches!(&svg.element().unwrap().children[0], Node::Cdata(cdata) if cdata.as_str() == "x")
+    );
+    Ok(())
+}
@@ -0,0 +1,87 @@
+use html_parser::{Dom, JsonOptions, JsonSchema, Node, ParseOptions, Result};
+use insta::assert_json_snapshot;
+
+#[test]
+fn it_parses_the_content_of_conditional_comments() -> Result<()> {
+    let html = "<div><!--[if gte mso 9]><table><tr><td>Outlook</td></tr></table><![endif]--></div>";
+    let dom = Dom::parse(html)?;
+    let div = dom.children[0].element().unwrap();
+    let comment = div.children[0].conditional_comment().unwrap();
+    assert_eq!(comment.condition, "gte mso 9");
+    assert!(!comment.downlevel_revealed);
+    assert_eq!(comment.children[0].element().unwrap().name, "table");
+    assert_eq!(
+        dom.span_text(&comment.source_span),
+        "<!--[if gte mso 9]><table><tr><td>Outlook</td></tr></table><![endif]-->"
+    );
+    assert_eq!(dom.to_html(), html);
+    Ok(())
+}
+
+#[test]
+fn it_parses_downlevel_revealed_conditional_comments() -> Result<()> {
+    let html = "<!--[if !mso]><!--><div class=\"web\">Web</div><!--<![endif]-->";
+    let dom = Dom::parse(html)?;
+    let comment = dom.children[0].conditional_comment().unwrap();
+    assert_eq!(comment.condition, "!mso");
+    assert!(comment.downlevel_revealed);
+    assert_eq!(comment.children[0].element().unwrap().classes, vec!["web"]);
+    assert_eq!(dom.to_html(), html);
+    Ok(())
+}
+
+#[test]
+fn it_keeps_conditional_comments_before_the_doctype() -> Result<()> {
+    let html = "<!--[if IE]><p>IE</p><![endif]--><!DOCTYPE html><html></html>";
+    let dom = Dom::parse(html)?;
+    assert!(matches!(dom.children[0], Node::ConditionalComment(_)));
+    assert!(dom.doctype().is_some());
+    assert_eq!(dom.to_html(), html);
+    Ok(())
+}
+
+#[test]
+fn it_visits_the_children_of_conditional_comments() -> Result<()> {
+    let mut dom = Dom::parse("<!--[if mso]><p>a</p><p>b</p><![endif]-->")?;
+    let texts = dom.children[0]
+        .into_iter()
+        .filter_map(|node| node.text())
+        .collect::<Vec<_>>();
+    assert_eq!(texts, vec!["a", "b"]);
+
+    dom.walk_mut(|node| {
+        if let Some(text) = node.text_mut() {
+            *text = text.to_uppercase();
+        }
+    });
+    assert_eq!(dom.to_html(), "<!--[if mso]><p>A</p><p>B</p><![endif]-->");
+
+    let arena = dom.clone().into_arena();
+    assert_eq!(arena.into_dom(), dom);
+    Ok(())
+}
+
+#[test]
+fn it_leaves_out_conditional_comments_without_comments() -> Result<()> {
+    let options = ParseOptions::new().keep_comments(false);
+    let dom = Dom::parse_with("<p>a<!--[if IE]>b<![endif]--></p>", &options)?;
+    assert_eq!(dom.to_html(), "<p>a</p>");
+
+    // The content of downlevel-revealed ones is shown by every browser
+    let html = "<p>a<!--[if !IE]><!--><b>b</b>c<!--<![endif]-->d</p>";
+    let dom = Dom::parse_with(html, &options)?;
+    assert_eq!(dom.to_html(), "<p>a<b>b</b>cd</p>");
+    Ok(())
+}
+
+#[test]
+fn it_serializes_conditional_comments_to_json() -> Result<()> {
+    let dom = Dom::parse(
+        "<!--[if mso]><p>Outlook</p><![endif]--><!--[if !mso]><!--><p>Web</p><!--<![endif]-->",
+    )?;
+    assert_json_snapshot!(dom);
+    let json = dom.to_json_with(&JsonOptions::new().schema(JsonSchema::Tagged))?;
+    assert_eq!(Dom::from_json(&json)?.to_html(), dom.to_html());
+    assert_eq!(Dom::from_json(&dom.to_json()?)?.to_html(), dom.to_html());
+    Ok(())
+}
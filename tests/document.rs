@@ -1,4 +1,4 @@
-use html_parser::{Dom, Result};
+use html_parser::{Dom, QuirksMode, Result};
 use indoc::indoc;
 use insta::assert_json_snapshot;
 
@@ -34,3 +34,57 @@ fn it_error_when_doctype_and_multiple_html() {
     let html = "<!DOCTYPE html><html></html><html></html>";
     assert!(Dom::parse(html).is_err());
 }
+
+#[test]
+fn it_keeps_the_doctype_as_a_node() -> Result<()> {
+    let html = indoc!(
+        r#"
+        <!-- comment -->
+        <!DOCTYPE HTML PUBLIC "-//W3C//DTD HTML 4.01//EN" "http://www.w3.org/TR/html4/strict.dtd">
+        <html></html>
+    "#
+    );
+    let dom = Dom::parse(html)?;
+    let doctype = dom.children[1].doctype().unwrap();
+    assert_eq!(dom.doctype(), Some(doctype));
+    assert_eq!(doctype.name.as_deref(), Some("html"));
+    assert_eq!(
+        doctype.public_id.as_deref(),
+        Some("-//W3C//DTD HTML 4.01//EN")
+    );
+    assert_eq!(
+        doctype.system_id.as_deref(),
+        Some("http://www.w3.org/TR/html4/strict.dtd")
+    );
+    assert!(dom
+        .span_text(&doctype.source_span)
+        .starts_with("<!DOCTYPE HTML PUBLIC"));
+    assert_eq!(
+        dom.to_html(),
+        "<!--comment--><!DOCTYPE html PUBLIC \"-//W3C//DTD HTML 4.01//EN\" \"http://www.w3.org/TR/html4/strict.dtd\"><html></html>"
+    );
+    Ok(())
+}
+
+#[test]
+fn it_derives_the_quirks_mode_from_the_doctype() -> Result<()> {
+    let quirks_mode = |html: &str| Dom::parse(html).map(|dom| dom.quirks_mode());
+    assert_eq!(
+        quirks_mode("<!DOCTYPE html><html></html>")?,
+        QuirksMode::NoQuirks
+    );
+    assert_eq!(
+        quirks_mode(
+            r#"<!DOCTYPE html PUBLIC "-//W3C//DTD XHTML 1.0 Transitional//EN" "http://www.w3.org/TR/xhtml1/DTD/xhtml1-transitional.dtd"><html></html>"#
+        )?,
+        QuirksMode::LimitedQuirks
+    );
+    assert_eq!(
+        quirks_mode(r#"<!DOCTYPE html PUBLIC "-//W3C//DTD HTML 3.2 Final//EN"><html></html>"#)?,
+        QuirksMode::Quirks
+    );
+    assert_eq!(quirks_mode("<!DOCTYPE><html></html>")?, QuirksMode::Quirks);
+    assert_eq!(quirks_mode("<html></html>")?, QuirksMode::Quirks);
+    assert_eq!(quirks_mode("<p>fragment</p>")?, QuirksMode::NoQuirks);
+    Ok(())
+}
@@ -1,4 +1,4 @@
-use html_parser::{Dom, Result};
+use html_parser::{Dom, ParseOptions, Result};
 use insta::assert_json_snapshot;
 
 #[test]
@@ -100,3 +100,131 @@ fn it_keeps_spaces_for_non_classes() -> Result<()> {
     assert_json_snapshot!(dom);
     Ok(())
 }
+
+#[test]
+fn it_decodes_character_references_in_attributes() -> Result<()> {
+    let html = "<a href='?a=1&amp;b=2&copy=3' title=&quot;hi&quot; class='&lt;a&gt; b'></a>";
+    let dom = Dom::parse(html)?;
+    let a = dom.children[0].element().unwrap();
+    assert_eq!(a.attributes["href"].as_deref(), Some("?a=1&b=2&copy=3"));
+    assert_eq!(a.attributes["title"].as_deref(), Some("\"hi\""));
+    assert_eq!(a.classes, vec!["<a>", "b"]);
+    Ok(())
+}
+
+#[test]
+fn it_can_keep_character_references_in_attributes() -> Result<()> {
+    let options = ParseOptions::new().decode_entities(false);
+    let dom = Dom::parse_with("<a href='?a=1&amp;b=2'></a>", &options)?;
+    let a = dom.children[0].element().unwrap();
+    assert_eq!(a.attributes["href"].as_deref(), Some("?a=1&amp;b=2"));
+    Ok(())
+}
+
+#[test]
+fn it_keeps_attributes_in_source_order() -> Result<()> {
+    let dom = Dom::parse("<div z=1 a=2 m=3></div>")?;
+    let div = dom.children[0].element().unwrap();
+    let keys = div
+        .attributes
+        .iter()
+        .map(|x| x.key.as_ref())
+        .collect::<Vec<_>>();
+    assert_eq!(keys, vec!["z", "a", "m"]);
+    assert_eq!(div.outer_html(), "<div z=\"1\" a=\"2\" m=\"3\"></div>");
+    Ok(())
+}
+
+#[test]
+fn it_keeps_id_and_class_in_source_order() -> Result<()> {
+    for html in [
+        r#"<a href="x" class="c" id="y"></a>"#,
+        r#"<a id="y" href="x" class="c"></a>"#,
+        r#"<a class="c" id="y" class="d" href="x"></a>"#,
+        r#"<a href="x" id="y" id="z"></a>"#,
+    ] {
+        assert_eq!(Dom::parse(html)?.to_html(), html);
+    }
+
+    let mut dom = Dom::parse(r#"<a href="x" class="c" id="y" id="z" title="t"></a>"#)?;
+    let a = dom.children[0].element_mut().unwrap();
+    a.remove_attribute("href");
+    a.set_attribute("id", Some("w"));
+    assert_eq!(a.outer_html(), r#"<a class="c" id="w" title="t"></a>"#);
+    a.remove_attribute("class");
+    a.set_attribute("rel", None);
+    assert_eq!(a.outer_html(), r#"<a id="w" title="t" rel></a>"#);
+    Ok(())
+}
+
+#[test]
+fn it_keeps_duplicate_attributes() -> Result<()> {
+    let dom = Dom::parse("<div id=a title=one id=b title=two></div>")?;
+    let div = dom.children[0].element().unwrap();
+    assert_eq!(div.id.as_deref(), Some("a"));
+    assert_eq!(div.attributes["title"].as_deref(), Some("one"));
+
+    let titles = div.attributes.get_all("title").collect::<Vec<_>>();
+    assert_eq!(titles.len(), 2);
+    assert_eq!(titles[1].value.as_deref(), Some("two"));
+
+    let duplicates = div.attributes.duplicates().collect::<Vec<_>>();
+    assert_eq!(duplicates.len(), 1);
+    assert_eq!(duplicates[0].key, "title");
+    assert_eq!(div.attributes["id"].as_deref(), Some("b"));
+    Ok(())
+}
+
+#[test]
+fn it_compares_attribute_keys_ignoring_case() -> Result<()> {
+    let html = "<div ID=a id=b Title=one title=two CLASS=c></div>";
+    let mut dom = Dom::parse(html)?;
+    assert!(Dom::parse_strict(html).is_err());
+    let div = dom.children[0].element_mut().unwrap();
+    assert_eq!(div.id.as_deref(), Some("a"));
+    assert_eq!(div.classes, vec!["c"]);
+    assert_eq!(
+        (div.id_position(), div.class_position()),
+        (Some(0), Some(4))
+    );
+    assert_eq!(div.attributes["TITLE"].as_deref(), Some("one"));
+    assert_eq!(div.attributes.get_all("title").count(), 2);
+    assert_eq!(div.attributes.duplicates().count(), 1);
+    assert_eq!(
+        serde_json::to_string(&div.attributes)?,
+        r#"{"id":"b","Title":"one"}"#
+    );
+
+    assert_eq!(div.remove_attribute("Id"), Some(Some("a".into())));
+    assert_eq!(div.remove_attribute("TITLE"), Some(Some("one".into())));
+    assert_eq!(div.outer_html(), r#"<div class="c"></div>"#);
+    Ok(())
+}
+
+#[test]
+fn it_has_a_source_span_for_each_attribute() -> Result<()> {
+    let dom = Dom::parse("<div\n  cat='mjau'  dog=woff\n  hidden ></div>")?;
+    let div = dom.children[0].element().unwrap();
+    let spans = div
+        .attributes
+        .iter()
+        .map(|x| {
+            let span = &x.source_span;
+            (
+                dom.span_text(span),
+                span.start_line,
+                span.start_column,
+                span.end_column,
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        spans,
+        vec![
+            ("cat='mjau'", 2, 3, 13),
+            ("dog=woff", 2, 15, 23),
+            ("hidden", 3, 3, 9)
+        ]
+    );
+    Ok(())
+}
@@ -0,0 +1,74 @@
+use html_parser::{
+    DanglingEndTags, Dom, Error, ParseError, ParseErrorKind, ParseOptions, Severity,
+};
+use indoc::indoc;
+
+fn parse_error(html: &str) -> ParseError {
+    match Dom::parse(html) {
+        Err(Error::Parsing(error)) => error,
+        other => panic!("expected a parse error, got {:?}", other),
+    }
+}
+
+#[test]
+fn it_has_the_position_and_expected_rules_of_syntax_errors() {
+    let error = parse_error("<div>\n  <!x");
+    assert_eq!(error.kind, ParseErrorKind::Syntax);
+    assert_eq!(error.severity, Severity::Error);
+    assert_eq!(error.span.start, 9);
+    assert_eq!(error.span.start_line, 2);
+    assert_eq!(error.span.start_column, 4);
+    assert!(error.expected.contains(&"element name".to_string()));
+    assert_eq!(
+        error.to_string(),
+        [
+            " --> 2:4",
+            "  |",
+            "2 |   <!x",
+            "  |    ^---",
+            "  |",
+            "  = expected element name or raw text element name (style or script)",
+        ]
+        .join("\n")
+    );
+}
+
+#[test]
+fn it_has_the_kind_and_span_of_validation_errors() {
+    let html = "<html></html>\n<html></html>";
+    let error = parse_error(html);
+    assert_eq!(error.kind, ParseErrorKind::InvalidFragment);
+    assert_eq!(error.span.text(html), "<html></html>");
+    assert_eq!(error.span.start_line, 2);
+    assert_eq!(
+        error.to_string(),
+        "A document fragment should not include html"
+    );
+}
+
+#[test]
+fn it_collects_errors_that_dont_stop_the_parsing() -> html_parser::Result<()> {
+    let html = "<div></div>\n</p>";
+    let options = ParseOptions::new().dangling_end_tags(DanglingEndTags::Report);
+    let dom = Dom::parse_with(html, &options)?;
+    assert_eq!(dom.errors.len(), 1);
+    let error = &dom.errors[0];
+    assert_eq!(error.kind, ParseErrorKind::DanglingEndTag);
+    assert_eq!(error.span.text(html), "</p>");
+    assert_eq!(error.span.start_line, 2);
+    assert_eq!(error.to_string(), "Dangling end tag: </p>");
+    Ok(())
+}
+
+#[test]
+fn it_can_render_an_error_with_a_snippet() {
+    let html = "<p>one</p>\n<p>two</p>\n  <!x";
+    let error = parse_error(html);
+    let expected = indoc! {"
+        error: expected element name or raw text element name (style or script)
+         --> 3:4
+          |
+        3 |   <!x
+          |    ^"};
+    assert_eq!(error.render(html), expected);
+}
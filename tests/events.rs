@@ -0,0 +1,220 @@
+use html_parser::{
+    Dom, Error, Event, Events, Limit, ParseErrorKind, ParseOptions, Result, SourceSpan, TagCase,
+};
+
+/// The events as short strings, ex: `<a href=/>` and `text: one`
+fn describe(events: impl Iterator<Item = Result<Event<'static>>>) -> Result<Vec<String>> {
+    events
+        .map(|event| {
+            Ok(match event? {
+                Event::Doctype(doctype) => doctype.to_string(),
+                Event::StartTag {
+                    name,
+                    attributes,
+                    self_closing,
+                    ..
+                } => {
+                    let attributes = attributes.iter().map(|attribute| match &attribute.value {
+                        Some(value) => format!(" {}={}", attribute.key, value),
+                        None => format!(" {}", attribute.key),
+                    });
+                    let end = if self_closing { "/>" } else { ">" };
+                    format!("<{}{}{}", name, attributes.collect::<String>(), end)
+                }
+                Event::EndTag { name, .. } => format!("</{}>", name),
+                Event::Text(text) => format!("text: {}", text),
+                Event::Comment(comment) => format!("comment: {}", comment),
+                Event::Cdata(cdata) => format!("cdata: {}", cdata),
+                Event::ProcessingInstruction(instruction) => {
+                    format!("pi: {} {}", instruction.target, instruction.data)
+                }
+                Event::ConditionalCommentStart { condition, .. } => format!("if: {}", condition),
+                Event::ConditionalCommentEnd { .. } => String::from("endif"),
+                event => format!("{:?}", event),
+            })
+        })
+        .collect()
+}
+
+#[test]
+fn it_reads_the_input_as_events() -> Result<()> {
+    // The whitespace around comments is part of them
+    let html = concat!(
+        "<!doctype html><?xml version=\"1.0\"?>",
+        "<p id=a class='b c' hidden>one &amp; <br/>two</P>\n",
+        "<!-- comment --><!--[if IE]><b>old</b><![endif]--><svg><![CDATA[x]]></svg>"
+    );
+    assert_eq!(
+        describe(Events::new(html))?,
+        [
+            "<!DOCTYPE html>",
+            "pi: xml version=\"1.0\"",
+            "<p id=a class=b c hidden>",
+            "text: one & ",
+            "<br/>",
+            "text: two",
+            "</P>",
+            "comment: comment",
+            "if: IE",
+            "<b>",
+            "text: old",
+            "</b>",
+            "endif",
+            "<svg>",
+            "cdata: x",
+            "</svg>",
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn it_reads_the_tags_as_they_are_written() -> Result<()> {
+    // The tree closes the first `p`, and reopens `b` in the second one
+    let html = "<p><b>one<p>two</b></i>";
+    assert_eq!(
+        describe(Events::new(html))?,
+        [
+            "<p>",
+            "<b>",
+            "text: one",
+            "<p>",
+            "text: two",
+            "</b>",
+            "</i>"
+        ]
+    );
+    assert_eq!(
+        Dom::parse(html)?.to_html(),
+        "<p><b>one</b></p><p><b>two</b></p>"
+    );
+    Ok(())
+}
+
+#[test]
+fn it_reads_raw_text_elements() -> Result<()> {
+    let html = "<script>a < b &amp;</script><title>&amp;</title><style></style><textarea>x";
+    assert_eq!(
+        describe(Events::new(html))?,
+        [
+            "<script>",
+            "text: a < b &amp;",
+            "</script>",
+            "<title>",
+            "text: &",
+            "</title>",
+            "<style>",
+            "</style>",
+            "<textarea>",
+            "text: x",
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn it_has_the_spans_of_the_tags() -> Result<()> {
+    let html = "<p>\n  <a href=\"/\">x</a >\n</p>";
+    let spans = Events::new(html)
+        .map(|event| {
+            Ok(match event? {
+                Event::StartTag { span, .. } | Event::EndTag { span, .. } => span,
+                Event::Text(text) => text.source_span,
+                event => panic!("unexpected event {:?}", event),
+            })
+        })
+        .collect::<Result<Vec<SourceSpan>>>()?;
+    let texts = spans.iter().map(|span| span.text(html)).collect::<Vec<_>>();
+    assert_eq!(
+        texts,
+        ["<p>", "\n  ", "<a href=\"/\">", "x", "</a >", "\n", "</p>"]
+    );
+    assert_eq!((spans[2].start_line, spans[2].start_column), (2, 3));
+    Ok(())
+}
+
+#[test]
+fn it_follows_the_options() -> Result<()> {
+    let options = ParseOptions::new()
+        .tag_case(TagCase::Lowercase)
+        .decode_entities(false)
+        .source_spans(false)
+        .keep_comments(false);
+    let html = "<DIV Title='&amp;'>&lt;<!---->";
+    let events = Events::with_options(html, &options).collect::<Result<Vec<_>>>()?;
+    match &events[0] {
+        Event::StartTag {
+            name,
+            attributes,
+            span,
+            ..
+        } => {
+            assert_eq!(name, "div");
+            assert_eq!(attributes["Title"], Some("&amp;".into()));
+            assert_eq!(span, &SourceSpan::default());
+        }
+        event => panic!("expected a start tag, got {:?}", event),
+    }
+    assert_eq!(
+        describe(events.into_iter().skip(1).map(Ok))?,
+        ["text: &lt;", "comment: "]
+    );
+    Ok(())
+}
+
+#[test]
+fn it_stops_at_the_first_error() {
+    let mut events = Events::new("<p>a</p>\n<![endif]><p>b</p>");
+    for _ in 0..4 {
+        assert!(events.next().unwrap().is_ok());
+    }
+    match events.next() {
+        Some(Err(Error::Parsing(error))) => {
+            assert_eq!(error.kind, ParseErrorKind::Syntax);
+            assert_eq!(error.span.start, 10);
+        }
+        other => panic!("expected a parse error, got {:?}", other.map(|x| x.ok())),
+    }
+    assert!(events.next().is_none());
+}
+
+#[test]
+fn it_stops_at_the_limits() {
+    let options = ParseOptions::new().max_input_size(10);
+    let mut events = Events::with_options("<p>a</p><p>b</p>", &options);
+    assert!(matches!(
+        events.next(),
+        Some(Err(Error::LimitExceeded(Limit::InputSize(10))))
+    ));
+    assert!(events.next().is_none());
+
+    let options = ParseOptions::new().max_attributes(1);
+    let mut events = Events::with_options("<p a>a</p><p a b>b</p>", &options);
+    assert_eq!(events.by_ref().take_while(Result::is_ok).count(), 3);
+    assert!(events.next().is_none());
+}
+
+#[test]
+fn it_reads_the_same_links_as_the_tree() -> Result<()> {
+    let html = include_str!("../benches/wikipedia-2020-12-21.html");
+    let mut links = vec![];
+    for event in Events::new(html) {
+        if let Event::StartTag {
+            name, attributes, ..
+        } = event?
+        {
+            if name == "a" {
+                links.extend(attributes.get("href").cloned().flatten());
+            }
+        }
+    }
+    let dom = Dom::parse(html)?;
+    let tree_links = dom
+        .select("a[href]")?
+        .into_iter()
+        .filter_map(|element| element.attributes.get("href").cloned().flatten())
+        .collect::<Vec<_>>();
+    assert!(links.len() > 100);
+    assert_eq!(links, tree_links);
+    Ok(())
+}
@@ -0,0 +1,128 @@
+use html_parser::{Dom, ParseErrorKind, Result, Severity};
+use indoc::indoc;
+use insta::assert_json_snapshot;
+
+#[test]
+fn it_closes_list_items() -> Result<()> {
+    let dom = Dom::parse("<ul><li>a<li>b</ul><ol><li>c<ul><li>d</ul><li>e</ol>")?;
+    assert_eq!(
+        dom.to_html(),
+        "<ul><li>a</li><li>b</li></ul><ol><li>c<ul><li>d</li></ul></li><li>e</li></ol>"
+    );
+    assert!(dom.errors.is_empty());
+    Ok(())
+}
+
+#[test]
+fn it_closes_paragraphs() -> Result<()> {
+    let dom = Dom::parse("<p>one<p>two<div>three</div><p>four")?;
+    assert_eq!(
+        dom.to_html(),
+        "<p>one</p><p>two</p><div>three</div><p>four</p>"
+    );
+    assert!(dom.errors.is_empty());
+    Ok(())
+}
+
+#[test]
+fn it_closes_definitions_and_options() -> Result<()> {
+    let dom = Dom::parse(
+        "<dl><dt>a<dd>b<dt>c</dl><select><optgroup><option>d<option>e<optgroup><option>f</select>",
+    )?;
+    assert_eq!(
+        dom.to_html(),
+        "<dl><dt>a</dt><dd>b</dd><dt>c</dt></dl><select><optgroup><option>d</option><option>e</option></optgroup><optgroup><option>f</option></optgroup></select>"
+    );
+    assert!(dom.errors.is_empty());
+    Ok(())
+}
+
+#[test]
+fn it_closes_table_rows_and_cells() -> Result<()> {
+    let dom = Dom::parse(
+        "<table><thead><tr><th>a<th>b<tbody><tr><td>1<td>2<tr><td><table><tr><td>3</table></table>",
+    )?;
+    assert_eq!(
+        dom.to_html(),
+        "<table><thead><tr><th>a</th><th>b</th></tr></thead><tbody><tr><td>1</td><td>2</td></tr><tr><td><table><tr><td>3</td></tr></table></td></tr></tbody></table>"
+    );
+    assert!(dom.errors.is_empty());
+    Ok(())
+}
+
+#[test]
+fn it_closes_head_and_body() -> Result<()> {
+    let html = indoc!(
+        "
+        <!DOCTYPE html>
+        <html>
+          <head>
+            <meta charset=\"utf-8\">
+            <title>Title</title>
+          <body>
+            <p>Text
+        </html>
+        "
+    );
+    let dom = Dom::parse(html)?;
+    assert_json_snapshot!(dom);
+    assert!(dom.errors.is_empty());
+    Ok(())
+}
+
+#[test]
+fn it_closes_unclosed_elements_at_the_end_of_the_input() -> Result<()> {
+    let html = "<div><span>text";
+    let dom = Dom::parse(html)?;
+    assert_eq!(dom.to_html(), "<div><span>text</span></div>");
+
+    let kinds = dom.errors.iter().map(|x| x.kind).collect::<Vec<_>>();
+    assert_eq!(kinds, vec![ParseErrorKind::UnclosedElement; 2]);
+    assert!(dom.errors.iter().all(|x| x.severity == Severity::Warning));
+    assert_eq!(dom.errors[0].to_string(), "Unclosed element: <span>");
+    assert_eq!(dom.errors[0].span.text(html), "<span>");
+    assert_eq!(dom.errors[1].span.text(html), "<div>");
+
+    let div = dom.children[0].element().unwrap();
+    assert_eq!(dom.span_text(&div.source_span), "<div><span>text");
+    assert_eq!(div.end_tag_span, None);
+    Ok(())
+}
+
+#[test]
+fn it_closes_the_elements_inside_an_end_tag() -> Result<()> {
+    let html = "<div><p>a<span>b</div>c";
+    let dom = Dom::parse(html)?;
+    assert_eq!(dom.to_html(), "<div><p>a<span>b</span></p></div>c");
+    // The end tag of a paragraph can be left out, but not the one of a span
+    assert_eq!(dom.errors.len(), 1);
+    assert_eq!(dom.errors[0].span.text(html), "<span>");
+
+    let p = dom.select("p")?[0];
+    assert_eq!(dom.span_text(&p.source_span), "<p>a<span>b");
+    assert_eq!(dom.span_text(p.content_span.as_ref().unwrap()), "a<span>b");
+    Ok(())
+}
+
+#[test]
+fn it_closes_the_elements_inside_a_conditional_comment() -> Result<()> {
+    let html = "<!--[if mso]><table><tr><td><![endif]-->text";
+    let dom = Dom::parse(html)?;
+    assert_eq!(
+        dom.to_html(),
+        "<!--[if mso]><table><tr><td></td></tr></table><![endif]-->text"
+    );
+    assert_eq!(dom.errors.len(), 1);
+    assert_eq!(dom.errors[0].span.text(html), "<table>");
+    Ok(())
+}
+
+#[test]
+fn it_doesnt_close_elements_outside_of_a_table_cell() -> Result<()> {
+    let dom = Dom::parse("<div><table><tr><td></div>a</td></tr></table></div>")?;
+    assert_eq!(
+        dom.to_html(),
+        "<div><table><tr><td>a</td></tr></table></div>"
+    );
+    Ok(())
+}
@@ -0,0 +1,200 @@
+use html_parser::{
+    DanglingEndTags, Dom, Error, JsonOptions, JsonSchema, Node, ParseOptions, Result,
+};
+use indoc::indoc;
+use insta::assert_snapshot;
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+
+static HTML: &str = indoc! {r#"
+    <!doctype html>
+    <html>
+        <body>
+            <!-- a comment -->
+            <p id="a" class="b c" title=one title=two>text &amp; more</p>
+            <br>
+        </body>
+    </html>
+"#};
+
+#[test]
+fn it_uses_the_untagged_schema_by_default() -> Result<()> {
+    let dom = Dom::parse(HTML)?;
+    assert_eq!(dom.to_json_with(&JsonOptions::new())?, dom.to_json()?);
+    assert_eq!(
+        dom.to_json_with(&JsonOptions::new().pretty(true))?,
+        dom.to_json_pretty()?
+    );
+    Ok(())
+}
+
+#[test]
+fn it_can_tell_text_and_comments_apart_with_the_tagged_schema() -> Result<()> {
+    let dom = Dom::parse(HTML)?;
+    let options = JsonOptions::new().schema(JsonSchema::Tagged).pretty(true);
+    assert_snapshot!(dom.to_json_with(&options)?);
+    Ok(())
+}
+
+#[test]
+fn it_can_include_source_spans_and_errors() -> Result<()> {
+    let html = "<p>a</p></div>";
+    let dom = Dom::parse_with(
+        html,
+        &ParseOptions::new().dangling_end_tags(DanglingEndTags::Report),
+    )?;
+    let options = JsonOptions::new()
+        .schema(JsonSchema::Tagged)
+        .source_spans(true)
+        .errors(true)
+        .pretty(true);
+    assert_snapshot!(dom.to_json_with(&options)?);
+    Ok(())
+}
+
+#[test]
+fn it_can_serialize_nodes_and_elements() -> Result<()> {
+    let dom = Dom::parse("<p>a<!--b--></p>")?;
+    let options = JsonOptions::new().schema(JsonSchema::Tagged);
+    let p = dom.children[0].element().unwrap();
+    assert_eq!(
+        p.children[1].to_json_with(&options)?,
+        r#"{"type":"comment","value":"b"}"#
+    );
+    assert_eq!(
+        Node::Text("a".into()).to_json_with(&JsonOptions::new())?,
+        r#""a""#
+    );
+    assert_eq!(
+        p.to_json_with(&options.source_spans(true))?,
+        r#"{"type":"element","name":"p","variant":"normal","children":[{"type":"text","value":"a","sourceSpan":{"start":3,"end":4,"startLine":1,"endLine":1,"startColumn":4,"endColumn":5}},{"type":"comment","value":"b","sourceSpan":{"start":4,"end":12,"startLine":1,"endLine":1,"startColumn":5,"endColumn":13}}],"sourceSpan":{"start":0,"end":16,"startLine":1,"endLine":1,"startColumn":1,"endColumn":17}}"#
+    );
+    Ok(())
+}
+
+/// How a snapshot was written, if it isn't a tree with the default json options
+#[derive(Clone, Copy, PartialEq)]
+enum Snapshot {
+    Debug,
+    Node,
+    Tagged,
+    TaggedWithSpansAndErrors,
+}
+
+const SNAPSHOTS: &[(&str, Snapshot)] = &[
+    (
+        "source_span__it_can_generate_source_span.snap",
+        Snapshot::Debug,
+    ),
+    ("element__it_can_clone_node.snap", Snapshot::Node),
+    (
+        "json__it_can_tell_text_and_comments_apart_with_the_tagged_schema.snap",
+        Snapshot::Tagged,
+    ),
+    (
+        "json__it_can_include_source_spans_and_errors.snap",
+        Snapshot::TaggedWithSpansAndErrors,
+    ),
+];
+
+/// Every json snapshot is read back, and written again with the same schema and options
+#[test]
+fn it_can_round_trip_the_json_snapshots() -> Result<()> {
+    let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/snapshots");
+    for (name, _) in SNAPSHOTS {
+        assert!(directory.join(name).exists(), "{}", name);
+    }
+
+    for entry in fs::read_dir(directory)? {
+        let path = entry?.path();
+        let name = path.file_name().unwrap().to_str().unwrap();
+        let snapshot = SNAPSHOTS
+            .iter()
+            .find(|(snapshot, _)| *snapshot == name)
+            .map(|(_, snapshot)| *snapshot);
+        if snapshot == Some(Snapshot::Debug) {
+            continue;
+        }
+
+        // The header of the snapshot is separated from the content by a line of dashes
+        let content = fs::read_to_string(&path)?;
+        let json = content.splitn(3, "---\n").nth(2).unwrap().trim_end();
+        let value = serde_json::from_str::<Value>(json)?;
+        let options = match snapshot {
+            Some(Snapshot::Tagged) => JsonOptions::new().schema(JsonSchema::Tagged),
+            Some(Snapshot::TaggedWithSpansAndErrors) => JsonOptions::new()
+                .schema(JsonSchema::Tagged)
+                .source_spans(true)
+                .errors(true),
+            _ => JsonOptions::new(),
+        };
+        let output = if snapshot == Some(Snapshot::Node) {
+            serde_json::from_str::<Node>(json)?.to_json_with(&options)?
+        } else {
+            Dom::from_json(json)?.to_json_with(&options)?
+        };
+        assert_eq!(
+            serde_json::from_str::<Value>(&output)?,
+            value,
+            "{}",
+            path.display()
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn it_can_round_trip_deep_trees() -> Result<()> {
+    // Up to the default `ParseOptions::max_depth`
+    for depth in [70, 512] {
+        let html = format!("{}x", "<div>".repeat(depth));
+        let dom = Dom::parse(&html)?;
+        for options in [
+            JsonOptions::new(),
+            JsonOptions::new().schema(JsonSchema::Tagged),
+        ] {
+            let json = dom.to_json_with(&options)?;
+            let read = Dom::from_json(&json)?;
+            assert_eq!(read.to_json_with(&options)?, json);
+            assert_eq!(read.to_html(), dom.to_html());
+        }
+    }
+
+    // Deeper trees would overflow the stack when they are dropped or serialized
+    for depth in [514, 100_000] {
+        let json = format!(
+            "{{\"children\":[{}{}]}}",
+            "{\"name\":\"div\",\"variant\":\"normal\",\"children\":[".repeat(depth),
+            "]}".repeat(depth)
+        );
+        assert!(matches!(Dom::from_json(&json), Err(Error::Serde(_))));
+    }
+    Ok(())
+}
+
+#[test]
+fn it_can_read_comments_and_repeated_attributes_from_the_tagged_schema() -> Result<()> {
+    let dom = Dom::parse(HTML)?;
+    let options = JsonOptions::new()
+        .schema(JsonSchema::Tagged)
+        .source_spans(true)
+        .errors(true);
+    let json = dom.to_json_with(&options)?;
+    let read = Dom::from_json(&json)?;
+    assert_eq!(read.to_html(), dom.to_html());
+    assert_eq!(read.to_json_with(&options)?, json);
+    assert_eq!(
+        read.select("body")?[0].children[0].comment(),
+        Some("a comment")
+    );
+    assert_eq!(read.select("p")?[0].attributes.get_all("title").count(), 2);
+
+    // Comments are read as text from the untagged schema
+    let read = Dom::from_json(&dom.to_json()?)?;
+    assert_eq!(
+        read.select("body")?[0].children[0].text(),
+        Some("a comment")
+    );
+    Ok(())
+}
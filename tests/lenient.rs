@@ -0,0 +1,56 @@
+use html_parser::{Dom, DomVariant, ParseErrorKind, ParseOptions, Severity};
+
+#[test]
+fn it_keeps_bogus_markup_as_comments() {
+    let html = "<div>\n  <!x>text</div>";
+    assert!(Dom::parse(html).is_err());
+
+    let dom = Dom::parse_lenient(html);
+    assert_eq!(dom.to_html(), "<div><!--x-->text</div>");
+    assert_eq!(dom.errors.len(), 1);
+    let error = &dom.errors[0];
+    assert_eq!(error.kind, ParseErrorKind::Syntax);
+    assert_eq!(error.severity, Severity::Warning);
+    assert_eq!(error.span.text(html), "<!x>");
+    assert_eq!(error.to_string(), "Bogus comment at line 2, column 3");
+}
+
+#[test]
+fn it_ends_unclosed_comments_at_the_end_of_the_input() {
+    let html = "<p>text</p><!-- <p>more</p>";
+    let dom = Dom::parse_lenient(html);
+    assert_eq!(dom.to_html(), "<p>text</p><!--<p>more</p>-->");
+    assert_eq!(
+        dom.errors[0].to_string(),
+        "Unclosed comment at line 1, column 12"
+    );
+
+    let options = ParseOptions::new().keep_comments(false);
+    let dom = Dom::parse_lenient_with(html, &options);
+    assert_eq!(dom.to_html(), "<p>text</p>");
+    assert_eq!(dom.errors.len(), 1);
+}
+
+#[test]
+fn it_collects_validation_errors() {
+    let html = "<html></html>\n<html></html>";
+    assert!(Dom::parse(html).is_err());
+
+    let dom = Dom::parse_lenient(html);
+    assert_eq!(dom.to_html(), "<html></html><html></html>");
+    assert_eq!(dom.errors.len(), 1);
+    assert_eq!(dom.errors[0].kind, ParseErrorKind::InvalidFragment);
+    assert_eq!(dom.errors[0].span.start_line, 2);
+
+    let dom = Dom::parse_lenient("<div></div><body></body><head></head>");
+    assert_eq!(dom.tree_type, DomVariant::DocumentFragment);
+    let kinds = dom.errors.iter().map(|x| x.kind).collect::<Vec<_>>();
+    assert_eq!(kinds, vec![ParseErrorKind::InvalidFragment; 2]);
+}
+
+#[test]
+fn it_parses_valid_html_like_the_default_parser() -> html_parser::Result<()> {
+    let html = "<!DOCTYPE html><html><head><title>a</title></head><body><p>b<!--c--></body></html>";
+    assert_eq!(Dom::parse_lenient(html), Dom::parse(html)?);
+    Ok(())
+}
@@ -0,0 +1,115 @@
+use html_parser::{Dom, Error, Limit, ParseErrorKind, ParseOptions, Result, Severity};
+use std::time::Duration;
+
+fn limit(html: &str, options: &ParseOptions) -> Limit {
+    match Dom::parse_with(html, options) {
+        Err(Error::LimitExceeded(limit)) => limit,
+        other => panic!("expected a limit to be exceeded, got {:?}", other),
+    }
+}
+
+#[test]
+fn it_limits_the_size_of_the_input() -> Result<()> {
+    let options = ParseOptions::new().max_input_size(1_000);
+    let html = "<p>a</p>".repeat(1_000);
+    assert_eq!(limit(&html, &options), Limit::InputSize(1_000));
+    assert!(Dom::parse_with(&html[..1_000], &options).is_ok());
+    match Dom::parse_strict_with(&html, &options) {
+        Err(Error::LimitExceeded(Limit::InputSize(1_000))) => (),
+        other => panic!("expected a limit to be exceeded, got {:?}", other),
+    }
+    Ok(())
+}
+
+#[test]
+fn it_parses_the_input_up_to_the_size_limit_in_lenient_mode() {
+    // The limit is in the middle of `é`
+    let options = ParseOptions::new().max_input_size(11);
+    let dom = Dom::parse_lenient_with("<p>abcdefgé</p>", &options);
+    assert_eq!(dom.to_html(), "<p>abcdefg</p>");
+    assert_eq!(dom.source, "<p>abcdefg");
+    let error = dom.errors.last().unwrap();
+    assert_eq!(error.kind, ParseErrorKind::LimitExceeded);
+    assert_eq!(error.severity, Severity::Error);
+    assert_eq!(error.span.start, 10);
+    assert_eq!(error.to_string(), "Input too large: more than 11 bytes");
+}
+
+#[test]
+fn it_limits_the_number_of_nodes() -> Result<()> {
+    let options = ParseOptions::new().max_nodes(1_000);
+    for repeated in ["<br>", "<p>a", "<div>", "a<!---->", "<b><i><u><s>x</p>"] {
+        let html = repeated.repeat(1_001);
+        assert_eq!(limit(&html, &options), Limit::Nodes(1_000), "{}", repeated);
+    }
+    let html = "<p>a</p>".repeat(500);
+    assert!(Dom::parse_with(&html, &options).is_ok());
+    Ok(())
+}
+
+#[test]
+fn it_keeps_the_nodes_up_to_the_limit_in_lenient_mode() {
+    let options = ParseOptions::new().max_nodes(3);
+    let dom = Dom::parse_lenient_with("<p>a</p><p>b</p><p>c</p>", &options);
+    assert_eq!(dom.to_html(), "<p>a</p><p>b</p>");
+    let error = dom.errors.last().unwrap();
+    assert_eq!(error.kind, ParseErrorKind::LimitExceeded);
+    assert_eq!(error.span.start, 11);
+    assert_eq!(error.to_string(), "Too many nodes: more than 3");
+}
+
+#[test]
+fn it_limits_the_number_of_attributes() -> Result<()> {
+    let options = ParseOptions::new().max_attributes(100);
+    let html = format!("<div {}></div>", "a ".repeat(101));
+    assert_eq!(limit(&html, &options), Limit::Attributes(100));
+    let html = format!("<script {}></script>", "a=\"\" ".repeat(101));
+    assert_eq!(limit(&html, &options), Limit::Attributes(100));
+    let html = format!("<div {}></div>", "a ".repeat(100));
+    assert!(Dom::parse_with(&html, &options).is_ok());
+
+    let html = format!("<p>a</p><br {}>", "a ".repeat(101));
+    let dom = Dom::parse_lenient_with(&html, &options);
+    assert_eq!(dom.to_html(), "<p>a</p>");
+    assert_eq!(dom.errors[0].span.start, 212);
+    Ok(())
+}
+
+#[test]
+fn it_limits_the_length_of_attributes() -> Result<()> {
+    let options = ParseOptions::new().max_attribute_length(1_000);
+    for attribute in ["a=\"{}\"", "a='{}'", "a={}", "{}", "{}=a"] {
+        let html = format!("<div {}>", attribute.replace("{}", &"x".repeat(1_001)));
+        assert_eq!(
+            limit(&html, &options),
+            Limit::AttributeLength(1_000),
+            "{}",
+            attribute
+        );
+    }
+    let html = format!("<div a=\"{}\">", "x".repeat(995));
+    assert!(Dom::parse_with(&html, &options).is_ok());
+    Ok(())
+}
+
+#[test]
+fn it_stops_parsing_when_the_time_runs_out() -> Result<()> {
+    let html = "<b><i><u><s>x</p>".repeat(20_000);
+    let options = ParseOptions::new().timeout(Duration::from_millis(1));
+    assert_eq!(
+        limit(&html, &options),
+        Limit::Timeout(Duration::from_millis(1))
+    );
+
+    let options = ParseOptions::new().timeout(Duration::ZERO);
+    let dom = Dom::parse_lenient_with("<p>a</p>", &options);
+    assert!(dom.children.is_empty());
+    assert_eq!(
+        dom.errors[0].to_string(),
+        "Parsing took too long: more than 0ns"
+    );
+
+    let options = ParseOptions::new().timeout(Duration::from_secs(60));
+    assert!(Dom::parse_with(&html, &options).is_ok());
+    Ok(())
+}
@@ -0,0 +1,184 @@
+use html_parser::{Dom, Element, ElementVariant, Node, Result};
+
+fn element(name: &str) -> Element<'_> {
+    Element {
+        name: name.into(),
+        variant: ElementVariant::Normal,
+        ..Element::default()
+    }
+}
+
+fn is_element(node: &Node, name: &str) -> bool {
+    matches!(node, Node::Element(e) if e.name == name)
+}
+
+#[test]
+fn it_can_remove_nodes() -> Result<()> {
+    let mut dom = Dom::parse("<div><script>x</script><p>a<script>y</script></p></div>")?;
+    let removed = dom.remove_where(|node| is_element(node, "script"));
+    assert_eq!(removed, 2);
+    assert_eq!(dom.to_html(), "<div><p>a</p></div>");
+    Ok(())
+}
+
+#[test]
+fn it_can_unwrap_elements() -> Result<()> {
+    let mut dom = Dom::parse("<div><span>a<span>b</span></span>c</div>")?;
+    let unwrapped = dom.unwrap_where(|element| element.name == "span");
+    assert_eq!(unwrapped, 2);
+    assert_eq!(dom.to_html(), "<div>abc</div>");
+    Ok(())
+}
+
+#[test]
+fn it_can_wrap_nodes() -> Result<()> {
+    let mut dom = Dom::parse("<div><img src=a.png><img src=b.png></div>")?;
+    let wrapped = dom.wrap_where(|node| is_element(node, "img"), &element("figure"));
+    assert_eq!(wrapped, 2);
+    assert_eq!(
+        dom.to_html(),
+        "<div><figure><img src=\"a.png\"></figure><figure><img src=\"b.png\"></figure></div>"
+    );
+    Ok(())
+}
+
+#[test]
+fn it_can_insert_before_and_after_nodes() -> Result<()> {
+    let mut dom = Dom::parse("<ul><li>a</li><li>b</li></ul>")?;
+    let hr = Node::Element(Element {
+        name: "hr".into(),
+        ..Element::default()
+    });
+    assert_eq!(
+        dom.insert_before_where(|node| is_element(node, "li"), &hr),
+        2
+    );
+    assert_eq!(
+        dom.insert_after_where(|node| is_element(node, "ul"), &Node::Text("end".into())),
+        1
+    );
+    assert_eq!(dom.to_html(), "<ul><hr><li>a</li><hr><li>b</li></ul>end");
+    Ok(())
+}
+
+#[test]
+fn it_can_replace_nodes() -> Result<()> {
+    let mut dom = Dom::parse("<p>a<!--x-->b</p>")?;
+    dom.replace_with(|node| match node {
+        Node::Comment(_) => vec![],
+        Node::Text(text) => vec![Node::Text(text.to_uppercase().into())],
+        node => vec![node],
+    });
+    assert_eq!(dom.to_html(), "<p>AB</p>");
+    Ok(())
+}
+
+#[test]
+fn it_can_walk_and_modify_nodes() -> Result<()> {
+    let mut dom =
+        Dom::parse("<div><a href='http://a.com'>a</a><p><a href='http://b.com'>b</a></p></div>")?;
+    dom.walk_mut(|node| {
+        if let Some(element) = node.element_mut() {
+            if element.name == "a" {
+                element.set_attribute("rel", Some("nofollow"));
+            }
+        }
+        if let Some(text) = node.text_mut() {
+            text.push('!');
+        }
+    });
+    assert_eq!(
+        dom.to_html(),
+        "<div><a href=\"http://a.com\" rel=\"nofollow\">a!</a><p><a href=\"http://b.com\" rel=\"nofollow\">b!</a></p></div>"
+    );
+    Ok(())
+}
+
+#[test]
+fn it_can_modify_within_an_element() -> Result<()> {
+    let mut dom = Dom::parse("<div><b>a</b></div><b>b</b>")?;
+    let div = dom.children[0].element_mut().unwrap();
+    div.unwrap_where(|element| element.name == "b");
+    assert_eq!(dom.to_html(), "<div>a</div><b>b</b>");
+    Ok(())
+}
+
+#[test]
+fn it_can_modify_a_node_at_an_index_path() -> Result<()> {
+    let mut dom = Dom::parse("<div>a<p>b<b>c</b></p></div><p>d</p>")?;
+    assert!(is_element(dom.node_at(&[0, 1, 1]).unwrap(), "b"));
+    assert!(dom.node_at(&[0, 0, 0]).is_none());
+    assert!(dom.node_at(&[2]).is_none());
+    assert!(dom.node_at(&[]).is_none());
+
+    let p = dom.node_at_mut(&[0, 1]).unwrap().children_mut().unwrap();
+    p.remove(0);
+    p.insert(1, Node::Element(element("br")));
+    assert_eq!(dom.to_html(), "<div>a<p><b>c</b><br></p></div><p>d</p>");
+
+    let div = dom.children[0].element_mut().unwrap();
+    *div.node_at_mut(&[0]).unwrap() = Node::Element(element("hr"));
+    assert_eq!(div.outer_html(), "<div><hr><p><b>c</b><br></p></div>");
+    Ok(())
+}
+
+#[test]
+fn it_keeps_id_and_classes_in_sync_with_attributes() -> Result<()> {
+    let mut dom = Dom::parse("<div id=a class='b c' title=t></div>")?;
+    let div = dom.children[0].element_mut().unwrap();
+
+    div.set_attribute("id", Some("x"));
+    div.set_attribute("class", Some("y  z"));
+    div.set_attribute("title", None);
+    assert_eq!(div.id.as_deref(), Some("x"));
+    assert_eq!(div.classes, vec!["y", "z"]);
+    assert!(!div.attributes.contains_key("id"));
+
+    div.add_class("w");
+    div.add_class("w");
+    assert!(div.remove_class("y"));
+    assert!(!div.remove_class("y"));
+    assert!(div.has_class("w"));
+    assert_eq!(div.outer_html(), "<div id=\"x\" class=\"z w\" title></div>");
+
+    assert_eq!(div.remove_attribute("id"), Some(Some("x".into())));
+    assert_eq!(div.remove_attribute("class"), Some(Some("z w".into())));
+    assert_eq!(div.remove_attribute("title"), Some(None));
+    assert_eq!(div.remove_attribute("title"), None);
+    assert_eq!(div.outer_html(), "<div></div>");
+    Ok(())
+}
+
+#[test]
+fn it_removes_repeated_id_and_class_attributes() -> Result<()> {
+    let mut dom = Dom::parse("<div id=a id=b class=x class=y></div><p id=a id=b class=x>")?;
+    let div = dom.children[0].element_mut().unwrap();
+    assert_eq!(div.remove_attribute("id"), Some(Some("a".into())));
+    assert_eq!(div.remove_attribute("class"), Some(Some("x".into())));
+    assert_eq!(div.outer_html(), "<div></div>");
+
+    let p = dom.children[1].element_mut().unwrap();
+    p.set_attribute("id", Some("c"));
+    assert_eq!(p.outer_html(), "<p id=\"c\" class=\"x\"></p>");
+    Ok(())
+}
+
+#[test]
+fn it_can_modify_deep_trees_without_overflowing_the_stack() {
+    let mut node = Node::Element(element("div"));
+    for _ in 1..100_000 {
+        let mut parent = element("div");
+        parent.children.push(node);
+        node = Node::Element(parent);
+    }
+    let mut dom = Dom::default();
+    dom.children.push(node);
+
+    let mut count = 0;
+    dom.walk_mut(|_| count += 1);
+    assert_eq!(count, 100_000);
+    assert_eq!(dom.remove_where(|_| false), 0);
+    // Unwrapping every element also keeps the tree from overflowing the stack when it's dropped
+    assert_eq!(dom.unwrap_where(|_| true), 100_000);
+    assert!(dom.children.is_empty());
+}
@@ -0,0 +1,113 @@
+use html_parser::{Dom, ParseErrorKind, Result, Severity};
+use insta::assert_json_snapshot;
+use std::time::{Duration, Instant};
+
+#[test]
+fn it_reopens_misnested_formatting_elements() -> Result<()> {
+    let html = "<b><i>x</b>y</i>";
+    let dom = Dom::parse(html)?;
+    assert_eq!(dom.to_html(), "<b><i>x</i></b><i>y</i>");
+    assert_eq!(dom.errors.len(), 1);
+    let error = &dom.errors[0];
+    assert_eq!(error.kind, ParseErrorKind::MisnestedElement);
+    assert_eq!(error.severity, Severity::Warning);
+    assert_eq!(error.to_string(), "Misnested end tag: </b>");
+    assert_eq!(error.span.text(html), "</b>");
+    Ok(())
+}
+
+#[test]
+fn it_moves_blocks_out_of_misnested_formatting_elements() -> Result<()> {
+    let dom = Dom::parse("<b>1<p>2</b>3</p>")?;
+    assert_eq!(dom.to_html(), "<b>1</b><p><b>2</b>3</p>");
+    assert_json_snapshot!(dom);
+
+    let dom = Dom::parse("<a href=\"/\">1<div>2<i>3</a>4</i>5</div>")?;
+    assert_eq!(
+        dom.to_html(),
+        "<a href=\"/\">1</a><div><a href=\"/\">2<i>3</i></a><i>4</i>5</div>"
+    );
+    Ok(())
+}
+
+#[test]
+fn it_closes_a_link_that_is_opened_in_a_link() -> Result<()> {
+    let dom = Dom::parse("<a href=\"/a\">a<a href=\"/b\">b</a>")?;
+    assert_eq!(dom.to_html(), "<a href=\"/a\">a</a><a href=\"/b\">b</a>");
+    Ok(())
+}
+
+#[test]
+fn it_reopens_formatting_elements_after_a_paragraph() -> Result<()> {
+    let dom = Dom::parse("<p><b>one</p>two</b>")?;
+    assert_eq!(dom.to_html(), "<p><b>one</b></p><b>two</b>");
+    Ok(())
+}
+
+#[test]
+fn it_doesnt_reopen_formatting_elements_in_another_table_cell() -> Result<()> {
+    let dom = Dom::parse("<table><tr><td><b>one</td><td>two</td></tr></table>")?;
+    assert_eq!(
+        dom.to_html(),
+        "<table><tr><td><b>one</b></td><td>two</td></tr></table>"
+    );
+    Ok(())
+}
+
+#[test]
+fn it_keeps_the_attributes_of_reopened_elements() -> Result<()> {
+    let dom = Dom::parse("<font color=\"red\" class=\"a\"><i>x</font>y")?;
+    assert_eq!(
+        dom.to_html(),
+        "<font color=\"red\" class=\"a\"><i>x</i></font><i>y</i>"
+    );
+    let dom = Dom::parse("<p><font color=\"red\">x</p>y")?;
+    assert_eq!(
+        dom.to_html(),
+        "<p><font color=\"red\">x</font></p><font color=\"red\">y</font>"
+    );
+    Ok(())
+}
+
+#[test]
+fn it_reopens_a_limited_number_of_formatting_elements() -> Result<()> {
+    // Every paragraph would reopen the `b` elements of all of the paragraphs before it
+    let html = (0..2_000)
+        .map(|i| format!("<p><b x={}>t", i))
+        .collect::<String>();
+    let start = Instant::now();
+    let dom = Dom::parse_lenient(&html);
+    assert!(start.elapsed() < Duration::from_secs(2));
+    assert_eq!(dom.select("p")?.len(), 2_000);
+    assert!(dom.select("b")?.len() <= 2_000 * 17);
+    assert!(dom.errors.len() <= 2_000 * 17);
+
+    // The innermost ones are reopened
+    let dom = Dom::parse(&html)?;
+    let last = dom.select("p:last-child")?[0];
+    assert_eq!(last.select("b[x='1999']")?.len(), 1);
+    assert_eq!(last.select("b[x='1983']")?.len(), 1);
+    assert!(last.select("b[x='1982']")?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn it_keeps_a_limited_number_of_active_formatting_elements() -> Result<()> {
+    // Every `b` would be compared with all of the ones before it
+    let same = "<b x=i>".repeat(20_000);
+    let other = (0..20_000)
+        .map(|i| format!("<b x={}>", i))
+        .collect::<String>();
+    for html in [same, other] {
+        let start = Instant::now();
+        let dom = Dom::parse(&html)?;
+        assert!(start.elapsed() < Duration::from_secs(2));
+        assert_eq!(dom.select("b")?.len(), 20_000);
+    }
+
+    // Only three of the same elements are reopened, as in browsers
+    let dom = Dom::parse("<p><b x=i><b x=i><b x=i><b x=i>t</p>u")?;
+    assert_eq!(dom.select("p + b > b > b > b")?.len(), 0);
+    assert_eq!(dom.select("p + b > b > b")?.len(), 1);
+    Ok(())
+}
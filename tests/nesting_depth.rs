@@ -0,0 +1,78 @@
+use html_parser::{Dom, Error, Node, ParseErrorKind, ParseOptions, Result, Severity};
+
+/// The number of nested elements and conditional comments
+fn depth(nodes: &[Node]) -> usize {
+    let mut depth = 0;
+    let mut level = nodes.iter().collect::<Vec<_>>();
+    while !level.is_empty() {
+        level.retain(|node| node.children().is_some());
+        depth += usize::from(!level.is_empty());
+        level = level
+            .into_iter()
+            .flat_map(|node| node.children().unwrap_or_default())
+            .collect();
+    }
+    depth
+}
+
+#[test]
+fn it_opens_elements_that_are_nested_too_deep_next_to_each_other() -> Result<()> {
+    let options = ParseOptions::new().max_depth(2);
+    let dom = Dom::parse_with("<div><p><span>a</span><b>b</b></p></div>", &options)?;
+    assert_eq!(dom.to_html(), "<div><p></p><span>a</span><b>b</b></div>");
+    let error = &dom.errors[0];
+    assert_eq!(error.kind, ParseErrorKind::NestingTooDeep);
+    assert_eq!(error.severity, Severity::Warning);
+    assert_eq!(error.span.start, 8);
+    assert_eq!(
+        error.to_string(),
+        "Nesting too deep: more than 2 open elements"
+    );
+    Ok(())
+}
+
+#[test]
+fn it_limits_the_depth_of_hostile_input() -> Result<()> {
+    let html = "<div>".repeat(20_000);
+    let dom = Dom::parse(&html)?;
+    assert_eq!(depth(&dom.children), 512);
+    let too_deep = dom
+        .errors
+        .iter()
+        .filter(|error| error.kind == ParseErrorKind::NestingTooDeep);
+    assert_eq!(too_deep.count(), 1);
+
+    // The tree can be walked without overflowing the stack
+    assert_eq!(dom.to_html().len(), "<div></div>".len() * 20_000);
+    assert!(dom.to_json()?.len() > 20_000);
+    assert_eq!(dom.clone(), dom);
+    Ok(())
+}
+
+#[test]
+fn it_limits_the_depth_of_conditional_comments_and_reopened_elements() -> Result<()> {
+    let options = ParseOptions::new().max_depth(8);
+    for repeated in [
+        "<!--[if x]>",
+        "<b><i><u><s>x</p>",
+        "<svg>",
+        "<table><tr><td>",
+    ] {
+        let html = repeated.repeat(1_000);
+        let dom = Dom::parse_with(&html, &options)?;
+        assert!(depth(&dom.children) <= 8, "{}", repeated);
+    }
+    Ok(())
+}
+
+#[test]
+fn it_stops_at_nesting_that_is_too_deep_in_strict_mode() {
+    let options = ParseOptions::new().max_depth(1);
+    match Dom::parse_strict_with("<div><div></div></div>", &options) {
+        Err(Error::Parsing(error)) => {
+            assert_eq!(error.kind, ParseErrorKind::NestingTooDeep);
+            assert_eq!(error.span.start, 5);
+        }
+        other => panic!("expected a parse error, got {:?}", other),
+    }
+}
@@ -31,3 +31,59 @@ fn it_can_output_complex_html_as_json() -> Result<()> {
     assert_json_snapshot!(dom);
     Ok(())
 }
+
+#[test]
+fn it_can_output_html() -> Result<()> {
+    let html = "<div id=\"a\" class=\"b c\"><p>Hello <b>world</b></p><!--comment--></div>";
+    assert_eq!(Dom::parse(html)?.to_html(), html);
+    Ok(())
+}
+
+#[test]
+fn it_can_output_void_elements_as_html() -> Result<()> {
+    let dom = Dom::parse("<div><br><img src=\"cat.png\" /><my-element/></div>")?;
+    assert_eq!(
+        dom.to_html(),
+        "<div><br><img src=\"cat.png\"><my-element /></div>"
+    );
+
+    // An element that is renamed to a void element loses its end tag and children
+    let mut dom = Dom::parse("<p>a</p>")?;
+    dom.children[0].element_mut().unwrap().name = "hr".into();
+    assert_eq!(dom.to_html(), "<hr>");
+    Ok(())
+}
+
+#[test]
+fn it_escapes_text_and_attributes_in_html() -> Result<()> {
+    let dom = Dom::parse("<div title='say \"hi\" & <bye>'>a < b</div>")?;
+    assert_eq!(
+        dom.to_html(),
+        "<div title=\"say &quot;hi&quot; &amp; <bye>\">a &lt; b</div>"
+    );
+    Ok(())
+}
+
+#[test]
+fn it_does_not_escape_raw_text_in_html() -> Result<()> {
+    let html = "<script>if (a < b && c) {}</script><style>a > b {}</style>";
+    assert_eq!(Dom::parse(html)?.to_html(), html);
+    Ok(())
+}
+
+#[test]
+fn it_can_output_inner_and_outer_html() -> Result<()> {
+    let dom = Dom::parse("<ul><li>one</li><li>two</li></ul>")?;
+    let ul = dom.children[0].element().unwrap();
+    assert_eq!(ul.inner_html(), "<li>one</li><li>two</li>");
+    assert_eq!(ul.outer_html(), "<ul><li>one</li><li>two</li></ul>");
+    assert_eq!(ul.children[0].to_html(), "<li>one</li>");
+    Ok(())
+}
+
+#[test]
+fn it_can_round_trip_character_references_through_html() -> Result<()> {
+    let html = "<p title=\"&quot;a&quot; &amp; b\">Fish &amp; chips &lt;3</p>";
+    assert_eq!(Dom::parse(html)?.to_html(), html);
+    Ok(())
+}
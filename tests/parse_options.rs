@@ -0,0 +1,87 @@
+use html_parser::{DanglingEndTags, Dom, ParseErrorKind, ParseOptions, Result, TagCase};
+
+#[test]
+fn it_can_keep_whitespace_text() -> Result<()> {
+    let html = "<ul>\n  <li>a</li> <li> </li>\n</ul>";
+    let options = ParseOptions::new().keep_whitespace_text(true);
+    let dom = Dom::parse_with(html, &options)?;
+    let ul = dom.children[0].element().unwrap();
+    let texts = ul
+        .children
+        .iter()
+        .filter_map(|x| x.text())
+        .collect::<Vec<_>>();
+    assert_eq!(texts, vec!["\n  ", " ", "\n"]);
+    assert_eq!(dom.select("li")?[1].children[0].text(), Some(" "));
+
+    let dom = Dom::parse(html)?;
+    assert_eq!(dom.children[0].element().unwrap().children.len(), 2);
+    Ok(())
+}
+
+#[test]
+fn it_can_remove_comments() -> Result<()> {
+    let html = "<!-- a --><div><!-- b -->text</div>";
+    let options = ParseOptions::new().keep_comments(false);
+    let dom = Dom::parse_with(html, &options)?;
+    assert_eq!(dom.to_html(), "<div>text</div>");
+    Ok(())
+}
+
+#[test]
+fn it_can_report_dangling_end_tags() -> Result<()> {
+    let html = "<div></span>text</div>";
+    let options = ParseOptions::new().dangling_end_tags(DanglingEndTags::Report);
+    let dom = Dom::parse_with(html, &options)?;
+    assert_eq!(dom.to_html(), "<div>text</div>");
+    assert_eq!(dom.errors.len(), 1);
+    assert_eq!(dom.errors[0].kind, ParseErrorKind::DanglingEndTag);
+    assert_eq!(dom.errors[0].span.text(html), "</span>");
+    assert_eq!(dom.errors[0].to_string(), "Dangling end tag: </span>");
+    Ok(())
+}
+
+#[test]
+fn it_can_keep_dangling_end_tags_as_text() -> Result<()> {
+    let html = "<div></span>text</div>";
+    let options = ParseOptions::new().dangling_end_tags(DanglingEndTags::KeepAsText);
+    let dom = Dom::parse_with(html, &options)?;
+    let div = dom.children[0].element().unwrap();
+    assert_eq!(div.children[0].text(), Some("</span>"));
+    assert!(dom.errors.is_empty());
+    Ok(())
+}
+
+#[test]
+fn it_can_lowercase_tag_names() -> Result<()> {
+    let html = "<DIV><Span>a</Span><BR></DIV>";
+    let options = ParseOptions::new().tag_case(TagCase::Lowercase);
+    let dom = Dom::parse_with(html, &options)?;
+    assert_eq!(dom.to_html(), "<div><span>a</span><br></div>");
+
+    let dom = Dom::parse(html)?;
+    assert_eq!(dom.children[0].element().unwrap().name, "DIV");
+    Ok(())
+}
+
+#[test]
+fn it_can_keep_id_and_class_in_the_attributes() -> Result<()> {
+    let html = "<div class='a b' id=x title=t></div>";
+    let options = ParseOptions::new().split_id_and_class(false);
+    let dom = Dom::parse_with(html, &options)?;
+    let div = dom.children[0].element().unwrap();
+    assert_eq!(div.id, None);
+    assert!(div.classes.is_empty());
+    let keys = div
+        .attributes
+        .iter()
+        .map(|x| x.key.as_ref())
+        .collect::<Vec<_>>();
+    assert_eq!(keys, vec!["class", "id", "title"]);
+    assert_eq!(
+        div.outer_html(),
+        "<div class=\"a b\" id=\"x\" title=\"t\"></div>"
+    );
+    assert_eq!(dom.select("div#x.b")?.len(), 1);
+    Ok(())
+}
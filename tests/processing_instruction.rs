@@ -0,0 +1,60 @@
+use html_parser::{Dom, DomVariant, Node, Result};
+use indoc::indoc;
+
+#[test]
+fn it_keeps_processing_instructions_before_the_doctype() -> Result<()> {
+    let html = indoc!(
+        r#"
+        <?xml version="1.0" encoding="UTF-8"?>
+        <?xml-stylesheet href="style.css" type="text/css"?>
+        <!DOCTYPE html>
+        <html></html>
+    "#
+    );
+    let dom = Dom::parse(html)?;
+    assert_eq!(dom.tree_type, DomVariant::Document);
+    let instructions = dom
+        .children
+        .iter()
+        .filter_map(|x| x.processing_instruction())
+        .map(|x| (x.target.as_ref(), x.data.as_ref()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        instructions,
+        vec![
+            ("xml", r#"version="1.0" encoding="UTF-8""#),
+            ("xml-stylesheet", r#"href="style.css" type="text/css""#)
+        ]
+    );
+    assert!(dom.children[2].doctype().is_some());
+    Ok(())
+}
+
+#[test]
+fn it_keeps_processing_instructions_in_elements() -> Result<()> {
+    let html = "<svg><?target data ?><rect/></svg>";
+    let dom = Dom::parse(html)?;
+    let nodes = dom.children[0].into_iter().collect::<Vec<_>>();
+    assert_eq!(nodes.len(), 2);
+    let instruction = nodes[0].processing_instruction().unwrap();
+    assert_eq!(instruction.target, "target");
+    assert_eq!(instruction.data, "data ");
+    assert_eq!(dom.span_text(&instruction.source_span), "<?target data ?>");
+    assert_eq!(dom.to_html(), "<svg><?target data ?><rect /></svg>");
+    Ok(())
+}
+
+#[test]
+fn it_can_serialize_processing_instructions() -> Result<()> {
+    let dom = Dom::parse("<?xml?><p>a</p>")?;
+    assert_eq!(dom.tree_type, DomVariant::DocumentFragment);
+    assert!(dom.errors.is_empty());
+    assert_eq!(dom.to_html(), "<?xml?><p>a</p>");
+    let json = dom.to_json()?;
+    assert!(json.contains(r#"{"type":"processingInstruction","target":"xml","data":""}"#));
+    assert!(matches!(
+        &Dom::from_json(&json)?.children[0],
+        Node::ProcessingInstruction(instruction) if instruction.target == "xml"
+    ));
+    Ok(())
+}
@@ -0,0 +1,161 @@
+use html_parser::{Dom, Element, Result};
+use indoc::indoc;
+use std::time::{Duration, Instant};
+
+fn ids<'a>(elements: Vec<&'a Element<'a>>) -> Vec<&'a str> {
+    elements
+        .into_iter()
+        .map(|element| element.id.as_deref().unwrap_or_default())
+        .collect()
+}
+
+fn dom() -> Dom<'static> {
+    let html = indoc!(
+        r#"
+        <div id="main" class="card">
+            <h1 id="title">Title</h1>
+            <p id="p1" class="intro lead">One</p>
+            <p id="p2">Two <a id="a1" href="https://example.com">link</a></p>
+            <ul id="list">
+                <li id="li1" lang="en-US"></li>
+                <li id="li2" data-x="a b c"></li>
+                <li id="li3"><span id="s1"></span></li>
+                <li id="li4"></li>
+            </ul>
+            <a id="a2" href="/relative">relative</a>
+        </div>
+        <div id="other"></div>
+    "#
+    );
+    Dom::parse(html).unwrap()
+}
+
+#[test]
+fn it_can_select_by_type_id_and_class() -> Result<()> {
+    let dom = dom();
+    assert_eq!(ids(dom.select("p")?), vec!["p1", "p2"]);
+    assert_eq!(ids(dom.select("#title")?), vec!["title"]);
+    assert_eq!(ids(dom.select(".intro")?), vec!["p1"]);
+    assert_eq!(ids(dom.select("p.intro.lead")?), vec!["p1"]);
+    assert_eq!(ids(dom.select("DIV")?), vec!["main", "other"]);
+    assert_eq!(dom.select("*")?.len(), 13);
+    Ok(())
+}
+
+#[test]
+fn it_can_select_by_attribute() -> Result<()> {
+    let dom = dom();
+    assert_eq!(ids(dom.select("[href]")?), vec!["a1", "a2"]);
+    assert_eq!(ids(dom.select("a[href^=http]")?), vec!["a1"]);
+    assert_eq!(ids(dom.select("a[href$='.com']")?), vec!["a1"]);
+    assert_eq!(ids(dom.select("a[href*=relat]")?), vec!["a2"]);
+    assert_eq!(ids(dom.select("[data-x~=b]")?), vec!["li2"]);
+    assert_eq!(ids(dom.select("[lang|=en]")?), vec!["li1"]);
+    assert_eq!(ids(dom.select("[lang=EN-us i]")?), vec!["li1"]);
+    assert_eq!(ids(dom.select("[id=p1]")?), vec!["p1"]);
+    assert_eq!(ids(dom.select("[class~=lead]")?), vec!["p1"]);
+    Ok(())
+}
+
+#[test]
+fn it_can_select_with_combinators() -> Result<()> {
+    let dom = dom();
+    assert!(dom.select("div.card > a[href^=http]")?.is_empty());
+    assert_eq!(ids(dom.select("div.card a[href^=http]")?), vec!["a1"]);
+    assert_eq!(ids(dom.select("#main > a")?), vec!["a2"]);
+    assert_eq!(ids(dom.select("h1 + p")?), vec!["p1"]);
+    assert_eq!(ids(dom.select("h1 ~ p")?), vec!["p1", "p2"]);
+    assert_eq!(ids(dom.select("div li > span")?), vec!["s1"]);
+    assert_eq!(
+        ids(dom.select("h1, #s1, p")?),
+        vec!["title", "p1", "p2", "s1"]
+    );
+    Ok(())
+}
+
+#[test]
+fn it_can_select_with_pseudo_classes() -> Result<()> {
+    let dom = dom();
+    assert_eq!(ids(dom.select("li:first-child")?), vec!["li1"]);
+    assert_eq!(ids(dom.select("li:last-child")?), vec!["li4"]);
+    assert_eq!(ids(dom.select("li:nth-child(2n)")?), vec!["li2", "li4"]);
+    assert_eq!(ids(dom.select("li:nth-child(odd)")?), vec!["li1", "li3"]);
+    assert_eq!(ids(dom.select("li:nth-child(-n+2)")?), vec!["li1", "li2"]);
+    assert_eq!(ids(dom.select("li:nth-last-child(1)")?), vec!["li4"]);
+    assert_eq!(ids(dom.select("p:first-of-type")?), vec!["p1"]);
+    assert_eq!(ids(dom.select("span:only-child")?), vec!["s1"]);
+    assert_eq!(
+        ids(dom.select("li:not(:first-child, #li4)")?),
+        vec!["li2", "li3"]
+    );
+    assert_eq!(ids(dom.select("div:root")?), vec!["main", "other"]);
+    assert_eq!(ids(dom.select("li:empty")?), vec!["li1", "li2", "li4"]);
+    Ok(())
+}
+
+#[test]
+fn it_counts_the_siblings_once_for_positional_pseudo_classes() -> Result<()> {
+    // Every `li` would count all of its siblings
+    let html = format!("<ul>{}<p></p></ul>", "<li></li>".repeat(20_000));
+    let dom = Dom::parse(&html)?;
+    let start = Instant::now();
+    assert_eq!(dom.select("li:last-child")?.len(), 0);
+    assert_eq!(dom.select("li:last-of-type")?.len(), 1);
+    assert_eq!(dom.select("li:nth-child(2n)")?.len(), 10_000);
+    assert_eq!(
+        dom.select("li:first-child + li:nth-last-of-type(19999)")?
+            .len(),
+        1
+    );
+    assert!(start.elapsed() < Duration::from_secs(2));
+    Ok(())
+}
+
+#[test]
+fn it_can_select_within_an_element() -> Result<()> {
+    let dom = dom();
+    let list = &dom.select("#list")?[0];
+    assert_eq!(ids(list.select("li")?), vec!["li1", "li2", "li3", "li4"]);
+    assert_eq!(ids(list.select("ul > li > span")?), vec!["s1"]);
+    assert!(list.select("ul")?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn it_doesnt_match_the_position_of_the_element_it_selects_within() -> Result<()> {
+    let dom = Dom::parse(r#"<ul><li>a</li><li class="s"><b>x</b></li></ul>"#)?;
+    let item = &dom.select("li.s")?[0];
+    assert!(dom.select("li:first-child > b")?.is_empty());
+    assert!(item.select("li:first-child > b")?.is_empty());
+    assert!(item.select("li + li > b")?.is_empty());
+    assert!(item.select(":root > b")?.is_empty());
+    assert_eq!(item.select("li > b:first-child")?.len(), 1);
+    Ok(())
+}
+
+#[test]
+fn it_can_select_within_conditional_comments() -> Result<()> {
+    let html = indoc!(
+        r#"
+        <div id="outer">
+            <!--[if mso]><table id="t1"><tr><td id="c1"></td></tr></table><p id="p1"></p><![endif]-->
+            <p id="p2"></p>
+        </div>
+    "#
+    );
+    let dom = Dom::parse(html)?;
+    assert_eq!(ids(dom.select("table")?), vec!["t1"]);
+    assert_eq!(ids(dom.select("#outer > table td")?), vec!["c1"]);
+    assert_eq!(ids(dom.select("table + p")?), vec!["p1"]);
+    assert_eq!(ids(dom.select("p:first-of-type")?), vec!["p1", "p2"]);
+    assert_eq!(ids(dom.select("div > p")?), vec!["p1", "p2"]);
+    Ok(())
+}
+
+#[test]
+fn it_errors_on_invalid_selectors() {
+    let dom = dom();
+    assert!(dom.select("div >").is_err());
+    assert!(dom.select("a[href").is_err());
+    assert!(dom.select("li:unknown").is_err());
+}
@@ -1,4 +1,4 @@
-use html_parser::{Dom, Result};
+use html_parser::{Dom, Node, ParseOptions, Result, SourceSpan};
 use indoc::indoc;
 use insta::assert_debug_snapshot;
 
@@ -14,3 +14,103 @@ fn it_can_generate_source_span() -> Result<()> {
     assert_debug_snapshot!(dom);
     Ok(())
 }
+
+#[test]
+fn it_can_get_the_text_of_a_source_span() -> Result<()> {
+    let html = "<p>å <b id=x>bold</b></p>";
+    let dom = Dom::parse(html)?;
+    let b = dom.select("b")?[0];
+    assert_eq!(b.source_span.start, 6);
+    assert_eq!(b.source_span.end, 22);
+    assert_eq!(b.source_span.start_column, 6);
+    assert_eq!(dom.span_text(&b.source_span), "<b id=x>bold</b>");
+    assert_eq!(b.source_span.text(html), "<b id=x>bold</b>");
+    Ok(())
+}
+
+#[test]
+fn it_can_disable_source_spans() -> Result<()> {
+    let options = ParseOptions::new().source_spans(false);
+    let dom = Dom::parse_with("<p><b>bold</b></p>", &options)?;
+    let b = dom.select("b")?[0];
+    assert_eq!(b.source_span, SourceSpan::default());
+    assert_eq!(dom.span_text(&b.source_span), "");
+    assert!(dom.source.is_empty());
+    Ok(())
+}
+
+#[test]
+fn it_has_source_spans_for_text_and_comments() -> Result<()> {
+    let html = "<p>a &amp; b<!-- note --></p>";
+    let dom = Dom::parse(html)?;
+    let p = dom.children[0].element().unwrap();
+    match (&p.children[0], &p.children[1]) {
+        (Node::Text(text), Node::Comment(comment)) => {
+            assert_eq!(text.as_str(), "a & b");
+            assert_eq!(dom.span_text(&text.source_span), "a &amp; b");
+            assert_eq!(comment.as_str(), "note");
+            assert_eq!(dom.span_text(&comment.source_span), "<!-- note -->");
+        }
+        _ => panic!("expected a text and a comment"),
+    }
+    Ok(())
+}
+
+#[test]
+fn it_has_source_spans_for_attribute_keys_and_values() -> Result<()> {
+    let html = "<div  cat = 'mjau' dog=woff hidden></div>";
+    let dom = Dom::parse(html)?;
+    let div = dom.children[0].element().unwrap();
+    let spans = div
+        .attributes
+        .iter()
+        .map(|x| {
+            (
+                dom.span_text(&x.key_span),
+                x.value_span.as_ref().map(|span| dom.span_text(span)),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        spans,
+        vec![
+            ("cat", Some("mjau")),
+            ("dog", Some("woff")),
+            ("hidden", None)
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn it_has_source_spans_for_start_and_end_tags() -> Result<()> {
+    let html = "<div title='a>b'>\n  <br>\n  <script> x </script>\n</div >";
+    let dom = Dom::parse(html)?;
+    let div = dom.children[0].element().unwrap();
+    assert_eq!(dom.span_text(&div.start_tag_span), "<div title='a>b'>");
+    assert_eq!(
+        div.end_tag_span.as_ref().map(|span| dom.span_text(span)),
+        Some("</div >")
+    );
+    assert_eq!(
+        div.content_span.as_ref().map(|span| dom.span_text(span)),
+        Some("\n  <br>\n  <script> x </script>\n")
+    );
+
+    let br = dom.select("br")?[0];
+    assert_eq!(dom.span_text(&br.start_tag_span), "<br>");
+    assert_eq!(br.end_tag_span, None);
+    assert_eq!(br.content_span, None);
+
+    let script = dom.select("script")?[0];
+    assert_eq!(dom.span_text(&script.start_tag_span), "<script>");
+    assert_eq!(
+        script.content_span.as_ref().map(|span| dom.span_text(span)),
+        Some(" x ")
+    );
+    assert_eq!(
+        script.end_tag_span.as_ref().map(|span| dom.span_text(span)),
+        Some("</script>")
+    );
+    Ok(())
+}
@@ -0,0 +1,66 @@
+use html_parser::{Dom, Error, ParseError, ParseErrorKind, ParseOptions, Severity};
+
+fn strict_error(html: &str) -> ParseError {
+    match Dom::parse_strict(html) {
+        Err(Error::Parsing(error)) => error,
+        other => panic!("expected a parse error, got {:?}", other),
+    }
+}
+
+#[test]
+fn it_parses_valid_html() -> html_parser::Result<()> {
+    let html = "<!DOCTYPE html><html><head><title>a</title></head><body><p>b<br><svg><path/></svg></body></html>";
+    assert_eq!(Dom::parse_strict(html)?, Dom::parse(html)?);
+    // End tags that can be left out aren't errors
+    assert!(Dom::parse_strict("<ul><li>a<li>b</ul><p>c")?.errors.is_empty());
+    Ok(())
+}
+
+#[test]
+fn it_stops_at_dropped_dangling_end_tags() {
+    let html = "<div>a</div>\n</span>";
+    assert!(Dom::parse(html).is_ok());
+    let error = strict_error(html);
+    assert_eq!(error.kind, ParseErrorKind::DanglingEndTag);
+    assert_eq!(error.severity, Severity::Error);
+    assert_eq!(error.span.text(html), "</span>");
+    assert_eq!(error.span.start_line, 2);
+}
+
+#[test]
+fn it_stops_at_duplicate_attributes() {
+    let html = "<div id=\"a\" class=\"b\" ID=\"c\"></div>";
+    let error = strict_error(html);
+    assert_eq!(error.kind, ParseErrorKind::DuplicateAttribute);
+    assert_eq!(error.to_string(), "Duplicate attribute: ID");
+    assert_eq!(error.span.text(html), "ID=\"c\"");
+}
+
+#[test]
+fn it_stops_at_self_closing_non_void_elements() {
+    let html = "<p><div/></p>";
+    let error = strict_error(html);
+    assert_eq!(error.kind, ParseErrorKind::SelfClosingNonVoidElement);
+    assert_eq!(error.to_string(), "Self-closing non-void element: <div/>");
+    assert_eq!(error.span.text(html), "<div/>");
+}
+
+#[test]
+fn it_stops_at_the_first_error_in_the_input() {
+    let html = "<div><span>a</div></p>";
+    let error = strict_error(html);
+    assert_eq!(error.kind, ParseErrorKind::UnclosedElement);
+    assert_eq!(error.span.text(html), "<span>");
+}
+
+#[test]
+fn it_has_positions_without_source_spans() {
+    let options = ParseOptions::new().source_spans(false);
+    match Dom::parse_strict_with("<b>a", &options) {
+        Err(Error::Parsing(error)) => {
+            assert_eq!(error.kind, ParseErrorKind::UnclosedElement);
+            assert_eq!((error.span.start, error.span.end), (0, 3));
+        }
+        other => panic!("expected a parse error, got {:?}", other),
+    }
+}
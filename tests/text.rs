@@ -1,4 +1,4 @@
-use html_parser::{Dom, Result};
+use html_parser::{Dom, ParseOptions, Result};
 use indoc::indoc;
 use insta::assert_json_snapshot;
 
@@ -58,3 +58,29 @@ fn it_can_parse_text_in_paragraph_with_weird_formatting() -> Result<()> {
     assert_json_snapshot!(dom);
     Ok(())
 }
+
+#[test]
+fn it_decodes_character_references_in_text() -> Result<()> {
+    let dom = Dom::parse("<p>Fish &amp; chips &lt;3 &#x27;&#39; &copy 2020&nbsp;</p>")?;
+    let p = dom.children[0].element().unwrap();
+    assert_eq!(p.children[0].text(), Some("Fish & chips <3 '' \u{a9} 2020\u{a0}"));
+    Ok(())
+}
+
+#[test]
+fn it_does_not_decode_character_references_in_script_and_style() -> Result<()> {
+    let dom = Dom::parse("<script>a &amp;&amp; b</script><title>a &amp; b</title>")?;
+    let script = dom.children[0].element().unwrap();
+    assert_eq!(script.children[0].text(), Some("a &amp;&amp; b"));
+    let title = dom.children[1].element().unwrap();
+    assert_eq!(title.children[0].text(), Some("a & b"));
+    Ok(())
+}
+
+#[test]
+fn it_can_keep_character_references_in_text() -> Result<()> {
+    let options = ParseOptions::new().decode_entities(false);
+    let dom = Dom::parse_with("a &amp; b", &options)?;
+    assert_eq!(dom.children[0].text(), Some("a &amp; b"));
+    Ok(())
+}
@@ -0,0 +1,166 @@
+use html_parser::{Dom, Error, ParseOptions};
+#[cfg(feature = "pest")]
+use html_parser::{Events, Tokenizer};
+use std::time::{Duration, Instant};
+
+#[test]
+fn it_reads_unclosed_start_tags_in_linear_time() {
+    // The pest grammar reads the rest of the input again from every `<`
+    for unclosed in ["<a b=", "<a b=\"", "<a b ", "<?a ", "<!--", "<script>"] {
+        let html = unclosed.repeat(20_000);
+        let start = Instant::now();
+        let dom = Dom::parse_lenient(&html);
+        assert!(!dom.children.is_empty());
+        assert!(start.elapsed() < Duration::from_secs(2), "{}", unclosed);
+    }
+}
+
+#[test]
+fn it_finds_the_positions_of_errors_in_linear_time_without_source_spans() {
+    let html = "<!x>a".repeat(20_000);
+    let options = ParseOptions::new().source_spans(false);
+    let start = Instant::now();
+    let dom = Dom::parse_lenient_with(&html, &options);
+    assert_eq!(dom.errors.len(), 20_000);
+    assert_eq!(dom.errors[19_999].span.start_column, 99_996);
+    assert!(start.elapsed() < Duration::from_secs(2));
+}
+
+#[test]
+fn it_stops_at_markup_that_cant_be_parsed() {
+    let error = match Dom::parse("<p>a</p>\n<![endif]>") {
+        Err(Error::Parsing(error)) => error,
+        other => panic!("expected a parse error, got {:?}", other),
+    };
+    assert_eq!(error.span.start, 10);
+    assert_eq!(
+        error.to_string(),
+        [
+            " --> 2:2",
+            "  |",
+            "2 | <![endif]>",
+            "  |  ^---",
+            "  |",
+            "  = expected element name or raw text element name (style or script)",
+        ]
+        .join("\n")
+    );
+}
+
+#[cfg(feature = "pest")]
+mod pest {
+    use super::*;
+
+    /// The trees of the default, lenient and strict modes, where syntax errors only have to be
+    /// found by both, as the grammar may report them at a later position
+    fn parse(html: &str, tokenizer: Tokenizer) -> [Option<Dom<'_>>; 3] {
+        let options = ParseOptions::new().tokenizer(tokenizer);
+        [
+            Dom::parse_with(html, &options).ok(),
+            Some(Dom::parse_lenient_with(html, &options)),
+            Dom::parse_strict_with(html, &options).ok(),
+        ]
+    }
+
+    fn assert_same_tree(html: &str) {
+        assert_eq!(
+            parse(html, Tokenizer::Streaming),
+            parse(html, Tokenizer::Pest),
+            "{:?}",
+            html
+        );
+    }
+
+    #[test]
+    fn it_prints_syntax_errors_like_the_grammar() {
+        let errors = [Tokenizer::Streaming, Tokenizer::Pest].map(|tokenizer| {
+            let options = ParseOptions::new().tokenizer(tokenizer);
+            match Dom::parse_with("<p>a</p>\n<![endif]>", &options) {
+                Err(error) => error.to_string(),
+                other => panic!("expected a parse error, got {:?}", other),
+            }
+        });
+        assert_eq!(errors[0], errors[1]);
+    }
+
+    #[test]
+    fn it_gives_the_same_tree_as_the_grammar() {
+        assert_same_tree(include_str!("../benches/wikipedia-2020-12-21.html"));
+    }
+
+    #[test]
+    fn it_gives_the_same_events_as_the_grammar() {
+        let html = include_str!("../benches/wikipedia-2020-12-21.html");
+        let events = |tokenizer| {
+            let options = ParseOptions::new().tokenizer(tokenizer);
+            Events::with_options(html, &options)
+                .map(Result::ok)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(events(Tokenizer::Streaming), events(Tokenizer::Pest));
+    }
+
+    #[test]
+    fn it_gives_the_same_tree_as_the_grammar_for_malformed_input() {
+        let parts = [
+            "<",
+            ">",
+            "/",
+            "/>",
+            "?>",
+            "<?",
+            "<!",
+            "<!--",
+            "-->",
+            " --",
+            "!-->",
+            " ",
+            "\n",
+            "=",
+            "\"",
+            "'",
+            "a",
+            "B",
+            "p",
+            "div",
+            "script",
+            "Title",
+            "textarea",
+            "</",
+            "<![CDATA[",
+            "]]>",
+            "]>",
+            "<!--[if ",
+            "IE",
+            "<![endif]",
+            "<!doctype",
+            "html",
+            "é",
+            "&amp;",
+            "svg",
+            "b",
+            "i",
+            "li",
+            "<x ",
+            "x=",
+            "y='",
+            "?",
+            "-",
+            "[",
+            "]",
+        ];
+        // A small linear congruential generator, so that failures can be reproduced
+        let mut state: u64 = 0x5eed;
+        let mut random = |bound: usize| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 33) as usize % bound
+        };
+        for _ in 0..20_000 {
+            let length = random(16);
+            let html: String = (0..length).map(|_| parts[random(parts.len())]).collect();
+            assert_same_tree(&html);
+        }
+    }
+}